@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+
+use crate::indicator::OhlcIndicator;
+use crate::sma_keeper::SmaKeeper;
+
+/// Average Directional Index with +DI/-DI and ADXR, smoothed with SMAs over `period` bars
+pub struct AdxKeeper {
+    period: usize,
+    prev_high: f64,
+    prev_low: f64,
+    prev_close: f64,
+    has_prev: bool,
+    tr_keeper: SmaKeeper,
+    plus_dm_keeper: SmaKeeper,
+    minus_dm_keeper: SmaKeeper,
+    dx_keeper: SmaKeeper,
+    plus_di: f64,
+    minus_di: f64,
+    adx_history: VecDeque<f64>,
+    timestamp_counter: u64,
+}
+
+impl AdxKeeper {
+    pub fn new(period: usize) -> Result<Self, String> {
+        if period < 1 {
+            return Err("ADX period at least 1".to_string());
+        }
+
+        Ok(AdxKeeper {
+            period,
+            prev_high: 0.0,
+            prev_low: 0.0,
+            prev_close: 0.0,
+            has_prev: false,
+            tr_keeper: SmaKeeper::new(period, 0, 0.0),
+            plus_dm_keeper: SmaKeeper::new(period, 0, 0.0),
+            minus_dm_keeper: SmaKeeper::new(period, 0, 0.0),
+            dx_keeper: SmaKeeper::new(period, 0, 0.0),
+            plus_di: 0.0,
+            minus_di: 0.0,
+            adx_history: VecDeque::with_capacity(period + 2),
+            timestamp_counter: 1,
+        })
+    }
+
+    pub fn add(&mut self, high: f64, low: f64, close: f64) {
+        if self.has_prev {
+            let up_move = high - self.prev_high;
+            let down_move = self.prev_low - low;
+            let plus_dm = if up_move > down_move && up_move > 0.0 {
+                up_move
+            } else {
+                0.0
+            };
+            let minus_dm = if down_move > up_move && down_move > 0.0 {
+                down_move
+            } else {
+                0.0
+            };
+            let tr = (high - low)
+                .max((high - self.prev_close).abs())
+                .max((low - self.prev_close).abs());
+
+            let ts = self.timestamp_counter;
+            let tr_avg = self.tr_keeper.add(ts, tr);
+            let plus_dm_avg = self.plus_dm_keeper.add(ts, plus_dm);
+            let minus_dm_avg = self.minus_dm_keeper.add(ts, minus_dm);
+
+            if tr_avg > 0.0 {
+                self.plus_di = 100.0 * plus_dm_avg / tr_avg;
+                self.minus_di = 100.0 * minus_dm_avg / tr_avg;
+            } else {
+                self.plus_di = 0.0;
+                self.minus_di = 0.0;
+            }
+
+            let di_sum = self.plus_di + self.minus_di;
+            let dx = if di_sum > 0.0 {
+                100.0 * (self.plus_di - self.minus_di).abs() / di_sum
+            } else {
+                0.0
+            };
+            let adx = self.dx_keeper.add(ts, dx);
+            self.timestamp_counter += 1;
+
+            self.adx_history.push_back(adx);
+            while self.adx_history.len() > self.period + 1 {
+                self.adx_history.pop_front();
+            }
+        }
+
+        self.prev_high = high;
+        self.prev_low = low;
+        self.prev_close = close;
+        self.has_prev = true;
+    }
+
+    /// Gets the current ADX
+    pub fn get(&self) -> f64 {
+        self.dx_keeper.get()
+    }
+
+    pub fn get_plus_di(&self) -> f64 {
+        self.plus_di
+    }
+
+    pub fn get_minus_di(&self) -> f64 {
+        self.minus_di
+    }
+
+    /// ADXR: average of the current ADX and the ADX `period` bars ago.
+    /// Returns the current ADX until enough history has accumulated.
+    pub fn get_adxr(&self) -> f64 {
+        if self.adx_history.len() <= self.period {
+            return self.get();
+        }
+        let adx_n_bars_ago = self.adx_history[self.adx_history.len() - 1 - self.period];
+        (self.get() + adx_n_bars_ago) / 2.0
+    }
+}
+
+impl OhlcIndicator for AdxKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low, close);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bars() -> Vec<(f64, f64, f64)> {
+        let mut bars = Vec::new();
+        for i in 0..12 {
+            let base = 100.0 + i as f64 * 2.0;
+            bars.push((base + 1.5, base - 1.0, base + 1.0));
+        }
+        for i in 0..6 {
+            let base = 122.0 - i as f64 * 1.5;
+            bars.push((base + 1.0, base - 2.0, base - 1.0));
+        }
+        bars
+    }
+
+    #[test]
+    fn test_adx_new() {
+        assert!(AdxKeeper::new(14).is_ok());
+        assert!(AdxKeeper::new(0).is_err());
+    }
+
+    #[test]
+    fn test_uptrend_directional_index() {
+        let mut keeper = AdxKeeper::new(5).unwrap();
+        for (high, low, close) in bars().into_iter().take(12) {
+            keeper.add(high, low, close);
+        }
+        assert!(keeper.get_plus_di() > keeper.get_minus_di());
+        assert!(keeper.get() > 50.0);
+    }
+
+    #[test]
+    fn test_adxr_is_average_of_two_adx_points() {
+        let period = 5;
+        let mut keeper = AdxKeeper::new(period).unwrap();
+        let mut adx_values = Vec::new();
+        for (high, low, close) in bars() {
+            keeper.add(high, low, close);
+            adx_values.push(keeper.get());
+            if adx_values.len() <= period + 1 {
+                // One bar only seeds the previous values, not enough ADX history yet
+                assert_eq!(keeper.get_adxr(), keeper.get());
+            }
+        }
+
+        let last = adx_values.len() - 1;
+        let expected = (adx_values[last] + adx_values[last - period]) / 2.0;
+        assert!((keeper.get_adxr() - expected).abs() < 1e-12);
+    }
+}
@@ -1,7 +1,33 @@
 use std::collections::{HashMap, VecDeque};
 
+use crate::common_utils::round_to;
+use crate::indicator::OhlcIndicator;
 use crate::sma_keeper::SmaKeeper;
 
+/// Number of past ATR values retained for `volatility_rank`
+const ATR_HISTORY_LEN: usize = 1000;
+
+/// Previous-bar reference price used in the true range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtrTrMode {
+    /// Raw previous close
+    #[default]
+    Standard,
+    /// Weighted previous close `(high + low + 2 * close) / 4`
+    WeightedClose,
+}
+
+/// Construction parameters of an `AtrKeeper`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AtrParams {
+    pub period: usize,
+    pub candle_period: usize,
+    pub tr_mode: AtrTrMode,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtrKeeper {
     period: usize,
     candle_period: usize,
@@ -9,11 +35,17 @@ pub struct AtrKeeper {
     low: VecDeque<f64>,
     close: VecDeque<f64>,
     atr_keeper: SmaKeeper,
+    atr_history: VecDeque<f64>,
     timestamp_counter: u64,
+    tr_mode: AtrTrMode,
 }
 
 impl AtrKeeper {
     pub fn new(period: usize, candle_period: usize) -> Result<Self, String> {
+        Self::with_mode(period, candle_period, AtrTrMode::Standard)
+    }
+
+    pub fn with_mode(period: usize, candle_period: usize, tr_mode: AtrTrMode) -> Result<Self, String> {
         if period < 2 {
             return Err("ATR Period at least 2".to_string());
         }
@@ -25,10 +57,34 @@ impl AtrKeeper {
             low: VecDeque::new(),
             close: VecDeque::new(),
             atr_keeper: SmaKeeper::new(period, 0, 0.0),
+            atr_history: VecDeque::new(),
             timestamp_counter: 1,
+            tr_mode,
         })
     }
 
+    /// Clears the bars and ATR history, keeping the period, candle period and TR mode
+    pub fn reset(&mut self) {
+        self.high.clear();
+        self.low.clear();
+        self.close.clear();
+        self.atr_keeper.reset();
+        self.atr_history.clear();
+        self.timestamp_counter = 1;
+    }
+
+    pub fn from_params(params: AtrParams) -> Result<Self, String> {
+        Self::with_mode(params.period, params.candle_period, params.tr_mode)
+    }
+
+    pub fn params(&self) -> AtrParams {
+        AtrParams {
+            period: self.period,
+            candle_period: self.candle_period,
+            tr_mode: self.tr_mode,
+        }
+    }
+
     pub fn get_tr(&self, high: f64, low: f64, prev_close: f64) -> f64 {
         let hl = high - low;
         let hc = (high - prev_close).abs();
@@ -36,9 +92,22 @@ impl AtrKeeper {
         hl.max(hc).max(lc)
     }
 
+    /// Reference close of the bar at `index` according to the TR mode
+    fn reference_close(&self, index: usize) -> f64 {
+        let close = self.close.get(index).copied().unwrap_or(0.0);
+        match self.tr_mode {
+            AtrTrMode::Standard => close,
+            AtrTrMode::WeightedClose => {
+                let high = self.high.get(index).copied().unwrap_or(0.0);
+                let low = self.low.get(index).copied().unwrap_or(0.0);
+                (high + low + 2.0 * close) / 4.0
+            }
+        }
+    }
+
     pub fn fast_get_tr(&self) -> f64 {
         let prev_close = if self.close.len() >= 2 {
-            self.close.get(self.close.len() - 2).copied().unwrap_or(0.0)
+            self.reference_close(self.close.len() - 2)
         } else {
             0.0
         };
@@ -49,6 +118,15 @@ impl AtrKeeper {
         )
     }
 
+    /// Latest true range as a percentage of the last close, 0.0 when there is no close
+    pub fn get_tr_percent(&self) -> f64 {
+        let last_close = self.close.back().copied().unwrap_or(0.0);
+        if last_close == 0.0 {
+            return 0.0;
+        }
+        100.0 * self.fast_get_tr() / last_close
+    }
+
     pub fn add(&mut self, high_val: f64, low_val: f64, close_val: f64) {
         self.high.push_back(high_val);
         self.low.push_back(low_val);
@@ -66,14 +144,26 @@ impl AtrKeeper {
         }
 
         if self.close.len() > 1 {
-            self.atr_keeper.add(self.timestamp_counter, self.fast_get_tr());
+            let atr = self.atr_keeper.add(self.timestamp_counter, self.fast_get_tr());
             self.timestamp_counter += 1;
+
+            self.atr_history.push_back(atr);
+            while self.atr_history.len() > ATR_HISTORY_LEN {
+                self.atr_history.pop_front();
+            }
+        }
+    }
+
+    fn last_reference_close(&self) -> f64 {
+        if self.close.is_empty() {
+            return 0.0;
         }
+        self.reference_close(self.close.len() - 1)
     }
 
     pub fn peek_next(&self, high_val: f64, low_val: f64) -> f64 {
         (self.atr_keeper.get() * (self.period - 1) as f64
-            + self.get_tr(high_val, low_val, self.close.back().copied().unwrap_or(0.0)))
+            + self.get_tr(high_val, low_val, self.last_reference_close()))
             / self.period as f64
     }
 
@@ -81,18 +171,87 @@ impl AtrKeeper {
         self.atr_keeper.get()
     }
 
+    /// Gets the ATR, None until a second bar gives the first true range
+    pub fn get_opt(&self) -> Option<f64> {
+        if self.atr_history.is_empty() {
+            return None;
+        }
+        Some(self.get())
+    }
+
+    /// Gets the current ATR rounded to the nearest multiple of `tick_size`
+    pub fn get_rounded(&self, tick_size: f64) -> f64 {
+        round_to(self.get(), tick_size)
+    }
+
+    /// Returns (upper, lower) bands at `price ± multiplier * ATR`
+    pub fn bands(&self, price: f64, multiplier: f64) -> (f64, f64) {
+        let offset = multiplier * self.get();
+        (price + offset, price - offset)
+    }
+
+    /// Returns (lower, upper) stop levels at `price ∓ multiplier * ATR`
+    pub fn stop_levels(&self, price: f64, multiplier: f64) -> (f64, f64) {
+        let (upper, lower) = self.bands(price, multiplier);
+        (lower, upper)
+    }
+
+    /// Percent of the previous ATR values within the last `lookback` that are below the
+    /// current ATR, in [0, 100]. Returns 0.0 with fewer than 2 values.
+    /// At most `ATR_HISTORY_LEN` (1000) values are retained.
+    pub fn volatility_rank(&self, lookback: usize) -> f64 {
+        let count = self.atr_history.len().min(lookback);
+        let current = match self.atr_history.back() {
+            Some(&current) if count >= 2 => current,
+            _ => return 0.0,
+        };
+
+        let below = self
+            .atr_history
+            .iter()
+            .rev()
+            .take(count)
+            .filter(|&&atr| atr < current)
+            .count();
+        100.0 * below as f64 / (count - 1) as f64
+    }
+
+    /// Fluctuant index in basis points, see `fluctuant_index_scaled`
     pub fn fluctuant_index(&self, day_average_atr: &HashMap<usize, f64>) -> f64 {
+        self.fluctuant_index_scaled(day_average_atr, 10000.0)
+    }
+
+    /// Difference between ATR relative to the last close and the day average ATR for this
+    /// candle period, multiplied by `scale` (10000.0 for basis points, 100.0 for percent)
+    pub fn fluctuant_index_scaled(&self, day_average_atr: &HashMap<usize, f64>, scale: f64) -> f64 {
         if self.close.is_empty() {
             return 1e-6;
         }
         let avg_atr = day_average_atr.get(&self.candle_period).copied().unwrap_or(0.0);
-        10000.0 * (self.atr_keeper.get() / self.close.back().copied().unwrap_or(0.0) - avg_atr)
+        scale * (self.atr_keeper.get() / self.close.back().copied().unwrap_or(0.0) - avg_atr)
+    }
+}
+
+impl OhlcIndicator for AtrKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low, close);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_atr_keeper_new() {
@@ -155,6 +314,21 @@ mod tests {
         assert!(index.is_finite());
     }
 
+    #[test]
+    fn test_fluctuant_index_scaled() {
+        let mut keeper = AtrKeeper::new(14, 60).unwrap();
+        keeper.add(110.0, 100.0, 105.0);
+        keeper.add(115.0, 105.0, 110.0);
+
+        let mut day_avg_atr = HashMap::new();
+        day_avg_atr.insert(60, 0.01);
+
+        let default_index = keeper.fluctuant_index(&day_avg_atr);
+        let percent_index = keeper.fluctuant_index_scaled(&day_avg_atr, 100.0);
+        assert!((percent_index - default_index * 100.0 / 10000.0).abs() < 1e-9);
+        assert_eq!(keeper.fluctuant_index_scaled(&day_avg_atr, 10000.0), default_index);
+    }
+
     #[test]
     fn test_fluctuant_index_empty() {
         let keeper = AtrKeeper::new(14, 60).unwrap();
@@ -162,5 +336,144 @@ mod tests {
         let index = keeper.fluctuant_index(&day_avg_atr);
         assert_eq!(index, 1e-6);
     }
-}
 
+    #[test]
+    fn test_bands() {
+        let mut keeper = AtrKeeper::new(2, 60).unwrap();
+        keeper.add(110.0, 100.0, 105.0);
+        keeper.add(110.0, 100.0, 105.0);
+        // TR = max(10, 5, 5) = 10, ATR over one TR = 10
+        assert_eq!(keeper.get(), 10.0);
+
+        let (upper, lower) = keeper.bands(105.0, 1.5);
+        assert_eq!(upper, 120.0);
+        assert_eq!(lower, 90.0);
+    }
+
+    #[test]
+    fn test_get_tr_percent() {
+        let keeper = AtrKeeper::new(14, 60).unwrap();
+        assert_eq!(keeper.get_tr_percent(), 0.0);
+
+        let mut low_price = AtrKeeper::new(14, 60).unwrap();
+        low_price.add(110.0, 100.0, 105.0);
+        low_price.add(110.0, 100.0, 100.0);
+        // TR = 10 on a close of 100
+        assert!((low_price.get_tr_percent() - 10.0).abs() < 1e-9);
+
+        let mut high_price = AtrKeeper::new(14, 60).unwrap();
+        high_price.add(1100.0, 1000.0, 1050.0);
+        high_price.add(1100.0, 1000.0, 1000.0);
+        // TR and close both scale by 10, so the percent is unchanged
+        assert!((high_price.get_tr_percent() - low_price.get_tr_percent()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_weighted_close_mode() {
+        let mut standard = AtrKeeper::new(2, 60).unwrap();
+        let mut weighted = AtrKeeper::with_mode(2, 60, AtrTrMode::WeightedClose).unwrap();
+        for keeper in [&mut standard, &mut weighted] {
+            keeper.add(110.0, 100.0, 101.0);
+            keeper.add(120.0, 115.0, 118.0);
+        }
+
+        // Standard: max(5, |120 - 101|, |115 - 101|) = 19
+        assert_eq!(standard.get(), 19.0);
+        // Weighted previous close (110 + 100 + 2 * 101) / 4 = 103: max(5, 17, 12) = 17
+        assert_eq!(weighted.get(), 17.0);
+    }
+
+    #[test]
+    fn test_stop_levels() {
+        let mut keeper = AtrKeeper::new(2, 60).unwrap();
+        keeper.add(110.0, 100.0, 105.0);
+        keeper.add(110.0, 100.0, 105.0);
+
+        let (stop_low, stop_high) = keeper.stop_levels(105.0, 2.0);
+        assert_eq!(stop_low, 85.0);
+        assert_eq!(stop_high, 125.0);
+        assert_eq!(105.0 - stop_low, stop_high - 105.0);
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let params = AtrParams {
+            period: 14,
+            candle_period: 60,
+            tr_mode: AtrTrMode::WeightedClose,
+        };
+        let keeper = AtrKeeper::from_params(params).unwrap();
+        assert_eq!(keeper.params(), params);
+
+        let invalid = AtrParams { period: 1, ..params };
+        assert!(AtrKeeper::from_params(invalid).is_err());
+    }
+
+    #[test]
+    fn test_volatility_rank() {
+        let mut keeper = AtrKeeper::new(3, 60).unwrap();
+        assert_eq!(keeper.volatility_rank(100), 0.0);
+
+        for _ in 0..20 {
+            keeper.add(101.0, 99.0, 100.0);
+        }
+        // Flat ATR has nothing below it
+        assert_eq!(keeper.volatility_rank(100), 0.0);
+
+        keeper.add(110.0, 95.0, 108.0);
+        assert_eq!(keeper.volatility_rank(100), 100.0);
+        assert_eq!(keeper.volatility_rank(5), 100.0);
+
+        // Volatility fading back keeps the rank high but below the spike
+        keeper.add(109.0, 107.0, 108.0);
+        let rank = keeper.volatility_rank(100);
+        assert!(rank > 90.0 && rank < 100.0);
+    }
+
+    #[test]
+    fn test_get_rounded() {
+        let mut keeper = AtrKeeper::new(3, 60).unwrap();
+        keeper.add(110.0, 100.0, 105.0);
+        keeper.add(110.3, 100.0, 105.0);
+        keeper.add(110.0, 99.9, 105.0);
+        // ATR = (10.3 + 10.1) / 2 = 10.2
+        assert_eq!(keeper.get_rounded(0.25), 10.25);
+        assert_eq!(keeper.get_rounded(0.01), 10.2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = AtrKeeper::new(3, 60).unwrap();
+        let (keeper, restored) = assert_serde_round_trip(keeper, |keeper, _, price| {
+            keeper.add(price + 1.0, price - 1.5, price)
+        });
+        assert_eq!(restored.get(), keeper.get());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || AtrKeeper::new(3, 60).unwrap(),
+            |keeper, _, price| keeper.add(price + 1.0, price - 1.0, price),
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get(), keeper.volatility_rank(10)),
+        );
+    }
+
+    #[test]
+    fn test_get_opt() {
+        let mut keeper = AtrKeeper::new(3, 60).unwrap();
+        assert_eq!(keeper.get_opt(), None);
+        keeper.add(101.0, 99.0, 100.0);
+        assert_eq!(keeper.get_opt(), None);
+
+        keeper.add(100.0, 100.0, 100.0);
+        assert_eq!(keeper.get_opt(), Some(0.0));
+        keeper.add(103.0, 100.0, 102.0);
+        assert_eq!(keeper.get_opt(), Some(keeper.get()));
+
+        keeper.reset();
+        assert_eq!(keeper.get_opt(), None);
+    }
+}
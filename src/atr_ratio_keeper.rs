@@ -0,0 +1,77 @@
+use crate::atr_keeper::AtrKeeper;
+use crate::indicator::OhlcIndicator;
+
+/// Ratio of a short-period ATR to a long-period ATR, above 1.0 when volatility expands
+pub struct AtrRatioKeeper {
+    short_atr: AtrKeeper,
+    long_atr: AtrKeeper,
+}
+
+impl AtrRatioKeeper {
+    pub fn new(short_period: usize, long_period: usize) -> Result<Self, String> {
+        Ok(AtrRatioKeeper {
+            short_atr: AtrKeeper::new(short_period, 0)?,
+            long_atr: AtrKeeper::new(long_period, 0)?,
+        })
+    }
+
+    pub fn add(&mut self, high: f64, low: f64, close: f64) {
+        self.short_atr.add(high, low, close);
+        self.long_atr.add(high, low, close);
+    }
+
+    /// Returns short ATR / long ATR, or 1.0 while the long ATR is zero
+    pub fn get(&self) -> f64 {
+        let long = self.long_atr.get();
+        if long == 0.0 {
+            return 1.0;
+        }
+        self.short_atr.get() / long
+    }
+}
+
+impl OhlcIndicator for AtrRatioKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low, close);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atr_ratio_new() {
+        assert!(AtrRatioKeeper::new(5, 20).is_ok());
+        assert!(AtrRatioKeeper::new(1, 20).is_err());
+    }
+
+    #[test]
+    fn test_empty_ratio() {
+        let keeper = AtrRatioKeeper::new(5, 20).unwrap();
+        assert_eq!(keeper.get(), 1.0);
+    }
+
+    #[test]
+    fn test_volatility_burst() {
+        let mut keeper = AtrRatioKeeper::new(3, 10).unwrap();
+        for _ in 0..12 {
+            keeper.add(101.0, 99.0, 100.0);
+        }
+        assert!((keeper.get() - 1.0).abs() < 1e-9);
+
+        // Wide bars enter the short window far faster than the long one
+        for _ in 0..3 {
+            keeper.add(110.0, 90.0, 100.0);
+        }
+        assert!(keeper.get() > 1.0);
+    }
+}
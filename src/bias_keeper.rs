@@ -0,0 +1,103 @@
+use crate::indicator::Indicator;
+use crate::sma_keeper::SmaKeeper;
+
+/// BIAS oscillator: percent distance of the price from its SMA, `100 * (price - sma) / sma`
+pub struct BiasKeeper {
+    sma_keeper: SmaKeeper,
+    bias: f64,
+    timestamp_counter: u64,
+}
+
+impl BiasKeeper {
+    pub fn new(period: usize) -> Self {
+        BiasKeeper {
+            sma_keeper: SmaKeeper::new(period, 0, 0.0),
+            bias: 0.0,
+            timestamp_counter: 1,
+        }
+    }
+
+    pub fn add(&mut self, price: f64) {
+        let sma = self.sma_keeper.add(self.timestamp_counter, price);
+        self.timestamp_counter += 1;
+
+        self.bias = if sma == 0.0 {
+            0.0
+        } else {
+            100.0 * (price - sma) / sma
+        };
+    }
+
+    /// Gets the bias percent, 0.0 while the SMA is zero
+    pub fn get(&self) -> f64 {
+        self.bias
+    }
+
+    pub fn size(&self) -> usize {
+        self.sma_keeper.size()
+    }
+
+    /// True when the bias is above `thresh` percent
+    pub fn is_overbought(&self, thresh: f64) -> bool {
+        self.bias > thresh
+    }
+
+    /// True when the bias is below `thresh` percent (pass a negative level, e.g. -5.0)
+    pub fn is_oversold(&self, thresh: f64) -> bool {
+        self.bias < thresh
+    }
+}
+
+impl Indicator for BiasKeeper {
+    fn update(&mut self, value: f64) -> f64 {
+        self.add(value);
+        self.get()
+    }
+
+    fn value(&self) -> f64 {
+        self.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bias_new() {
+        let keeper = BiasKeeper::new(6);
+        assert_eq!(keeper.get(), 0.0);
+        assert_eq!(keeper.size(), 0);
+    }
+
+    #[test]
+    fn test_bias_above_sma() {
+        let mut keeper = BiasKeeper::new(3);
+        keeper.add(97.5);
+        keeper.add(97.5);
+        keeper.add(105.0);
+        // SMA = 100, price 5% above it
+        assert!((keeper.get() - 5.0).abs() < 1e-9);
+        assert!(keeper.is_overbought(4.0));
+        assert!(!keeper.is_oversold(-4.0));
+    }
+
+    #[test]
+    fn test_bias_below_sma() {
+        let mut keeper = BiasKeeper::new(3);
+        keeper.add(102.5);
+        keeper.add(102.5);
+        keeper.add(95.0);
+        assert!((keeper.get() + 5.0).abs() < 1e-9);
+        assert!(keeper.is_oversold(-4.0));
+        assert!(!keeper.is_overbought(4.0));
+    }
+
+    #[test]
+    fn test_zero_sma() {
+        let mut keeper = BiasKeeper::new(2);
+        keeper.add(1.0);
+        keeper.add(-1.0);
+        assert_eq!(keeper.get(), 0.0);
+    }
+}
@@ -1,26 +1,46 @@
-use std::collections::VecDeque;
-
 use crate::sma_keeper::SmaKeeper;
 
+/// Construction parameters of a `BollingerBandKeeper`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BollingerParams {
+    pub window_size: usize,
+    pub std_dev_multiplier: f64,
+    pub sample_std: bool,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BollingerBandKeeper {
-    arr: VecDeque<f64>,
     sma_keeper: SmaKeeper,
     window_size: usize,
     std_dev_multiplier: f64,
+    std_dev: f64,
+    sample_std: bool,
     upper_band: f64,
     lower_band: f64,
+    above_upper_count: usize,
+    below_lower_count: usize,
     timestamp_counter: u64,
 }
 
+impl Default for BollingerBandKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl BollingerBandKeeper {
     pub fn new() -> Self {
         BollingerBandKeeper {
-            arr: VecDeque::new(),
             sma_keeper: SmaKeeper::new(1, 0, 0.0),
             window_size: 1,
             std_dev_multiplier: 2.0,
+            std_dev: 0.0,
+            sample_std: false,
             upper_band: 0.0,
             lower_band: 0.0,
+            above_upper_count: 0,
+            below_lower_count: 0,
             timestamp_counter: 1,
         }
     }
@@ -31,12 +51,15 @@ impl BollingerBandKeeper {
         window_values: Option<Vec<f64>>,
     ) -> Self {
         let mut keeper = BollingerBandKeeper {
-            arr: VecDeque::new(),
             sma_keeper: SmaKeeper::new(window_size, 0, 0.0),
             window_size,
             std_dev_multiplier,
+            std_dev: 0.0,
+            sample_std: false,
             upper_band: 0.0,
             lower_band: 0.0,
+            above_upper_count: 0,
+            below_lower_count: 0,
             timestamp_counter: 1,
         };
 
@@ -49,36 +72,131 @@ impl BollingerBandKeeper {
         keeper
     }
 
-    pub fn size(&self) -> usize {
-        self.arr.len()
+    pub fn from_params(params: BollingerParams) -> Self {
+        let mut keeper = Self::with_window(params.window_size, params.std_dev_multiplier, None);
+        keeper.sample_std = params.sample_std;
+        keeper
     }
 
-    pub fn add(&mut self, value: f64) {
-        self.arr.push_back(value);
-        while self.arr.len() > self.window_size {
-            self.arr.pop_front();
+    pub fn params(&self) -> BollingerParams {
+        BollingerParams {
+            window_size: self.window_size,
+            std_dev_multiplier: self.std_dev_multiplier,
+            sample_std: self.sample_std,
         }
+    }
+
+    pub fn size(&self) -> usize {
+        self.sma_keeper.size()
+    }
+
+    pub fn get_timestamp_counter(&self) -> u64 {
+        self.timestamp_counter
+    }
+
+    /// Clears the window and bands, keeping the window size and multiplier
+    pub fn reset(&mut self) {
+        self.sma_keeper.reset();
+        self.std_dev = 0.0;
+        self.upper_band = 0.0;
+        self.lower_band = 0.0;
+        self.above_upper_count = 0;
+        self.below_lower_count = 0;
+        self.timestamp_counter = 1;
+    }
 
+    pub fn add(&mut self, value: f64) {
+        self.update_touch_counts(value);
         self.sma_keeper.add(self.timestamp_counter, value);
         self.timestamp_counter += 1;
-        let mean = self.sma_keeper.get();
-
-        let mut sq_sum = 0.0;
-        for i in 0..self.arr.len() {
-            let diff = self.arr[i] - mean;
-            sq_sum += diff * diff;
-        }
+        self.update_std();
+        self.update_bands();
+    }
 
-        let variance = if self.arr.is_empty() {
-            0.0
+    /// Takes the std from the SMA keeper's running Welford moments over the same window
+    fn update_std(&mut self) {
+        self.std_dev = if self.sample_std {
+            self.sma_keeper.sample_std()
         } else {
-            sq_sum / self.arr.len() as f64
+            self.sma_keeper.std()
         };
+    }
+
+    pub fn is_sample_std(&self) -> bool {
+        self.sample_std
+    }
+
+    /// Selects Bessel-corrected sample std (n - 1) instead of population std (n) and
+    /// recomputes the current bands
+    pub fn set_sample_std(&mut self, sample_std: bool) {
+        self.sample_std = sample_std;
+        self.update_std();
+        self.update_bands();
+    }
+
+    /// Counts consecutive values beyond the bands as they stood before `value` was added
+    fn update_touch_counts(&mut self, value: f64) {
+        if self.sma_keeper.size() == 0 {
+            return;
+        }
+        if self.is_above_upper_band(value) {
+            self.above_upper_count += 1;
+        } else {
+            self.above_upper_count = 0;
+        }
+        if self.is_below_lower_band(value) {
+            self.below_lower_count += 1;
+        } else {
+            self.below_lower_count = 0;
+        }
+    }
+
+    /// Number of consecutive values that closed above the prior bar's upper band
+    pub fn consecutive_above_upper(&self) -> usize {
+        self.above_upper_count
+    }
+
+    /// Number of consecutive values that closed below the prior bar's lower band
+    pub fn consecutive_below_lower(&self) -> usize {
+        self.below_lower_count
+    }
 
-        let stddev = variance.sqrt();
+    fn update_bands(&mut self) {
+        let mean = self.sma_keeper.get();
+        self.upper_band = mean + self.std_dev_multiplier * self.std_dev;
+        self.lower_band = mean - self.std_dev_multiplier * self.std_dev;
+    }
+
+    pub fn get_multiplier(&self) -> f64 {
+        self.std_dev_multiplier
+    }
+
+    /// Changes the std multiplier and recomputes the current bands from the stored std
+    pub fn set_multiplier(&mut self, std_dev_multiplier: f64) {
+        self.std_dev_multiplier = std_dev_multiplier;
+        self.update_bands();
+    }
+
+    pub fn get_upper_band(&self) -> f64 {
+        self.upper_band
+    }
+
+    pub fn get_lower_band(&self) -> f64 {
+        self.lower_band
+    }
+
+    pub fn get_middle_band(&self) -> f64 {
+        self.sma_keeper.get()
+    }
 
-        self.upper_band = mean + self.std_dev_multiplier * stddev;
-        self.lower_band = mean - self.std_dev_multiplier * stddev;
+    /// Band width relative to the middle band, `(upper - lower) / middle`, for squeeze detection.
+    /// Returns 0.0 while the middle band is zero.
+    pub fn get_bandwidth(&self) -> f64 {
+        let middle = self.get_middle_band();
+        if middle == 0.0 {
+            return 0.0;
+        }
+        (self.upper_band - self.lower_band) / middle
     }
 
     pub fn is_above_upper_band(&self, value: f64) -> bool {
@@ -92,11 +210,40 @@ impl BollingerBandKeeper {
     pub fn is_inside_band(&self, value: f64) -> bool {
         value >= self.lower_band && value <= self.upper_band
     }
+
+    /// Distance of `value` beyond the bands as a fraction of band width:
+    /// positive above the upper band, negative below the lower band, 0.0 inside
+    pub fn distance_beyond_band(&self, value: f64) -> f64 {
+        let width = self.upper_band - self.lower_band;
+        if width == 0.0 {
+            return 0.0;
+        }
+        if value > self.upper_band {
+            (value - self.upper_band) / width
+        } else if value < self.lower_band {
+            (value - self.lower_band) / width
+        } else {
+            0.0
+        }
+    }
+
+    /// %B: position of `value` relative to the bands, 0.0 at the lower band and 1.0 at the upper.
+    /// Returns None when the bands have zero width.
+    pub fn percent_b(&self, value: f64) -> Option<f64> {
+        let width = self.upper_band - self.lower_band;
+        if width == 0.0 {
+            return None;
+        }
+        Some((value - self.lower_band) / width)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_bollinger_band_new() {
@@ -140,5 +287,233 @@ mod tests {
         let mid_value = (keeper.upper_band + keeper.lower_band) / 2.0;
         assert!(keeper.is_inside_band(mid_value));
     }
-}
 
+    #[test]
+    fn test_reset_timestamp_counter() {
+        let mut keeper = BollingerBandKeeper::with_window(5, 2.0, None);
+        keeper.add(100.0);
+        keeper.add(101.0);
+        keeper.add(102.0);
+        assert_eq!(keeper.get_timestamp_counter(), 4);
+
+        keeper.reset();
+        assert_eq!(keeper.get_timestamp_counter(), 1);
+        assert_eq!(keeper.size(), 0);
+        assert_eq!(keeper.upper_band, 0.0);
+        assert_eq!(keeper.lower_band, 0.0);
+    }
+
+    #[test]
+    fn test_set_multiplier() {
+        let mut keeper = BollingerBandKeeper::with_window(5, 2.0, Some(vec![100.0, 101.0, 102.0]));
+        assert_eq!(keeper.get_multiplier(), 2.0);
+        let mean = 101.0;
+        let half_width = keeper.upper_band - mean;
+
+        keeper.set_multiplier(3.0);
+        assert_eq!(keeper.get_multiplier(), 3.0);
+        assert!((keeper.upper_band - mean - half_width * 1.5).abs() < 1e-9);
+        assert!((mean - keeper.lower_band - half_width * 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distance_beyond_band() {
+        let keeper = BollingerBandKeeper::with_window(5, 2.0, Some(vec![100.0, 101.0, 102.0]));
+        let width = keeper.upper_band - keeper.lower_band;
+
+        let above = keeper.upper_band + width * 0.25;
+        assert!((keeper.distance_beyond_band(above) - 0.25).abs() < 1e-9);
+
+        let below = keeper.lower_band - width * 0.5;
+        assert!((keeper.distance_beyond_band(below) + 0.5).abs() < 1e-9);
+
+        assert_eq!(keeper.distance_beyond_band(101.0), 0.0);
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let params = BollingerParams {
+            window_size: 20,
+            std_dev_multiplier: 2.5,
+            sample_std: true,
+        };
+        let keeper = BollingerBandKeeper::from_params(params);
+        assert_eq!(keeper.params(), params);
+    }
+
+    #[test]
+    fn test_consecutive_band_touches() {
+        let mut keeper =
+            BollingerBandKeeper::with_window(5, 1.0, Some(vec![100.0, 101.0, 100.0, 101.0, 100.0]));
+        assert_eq!(keeper.consecutive_above_upper(), 0);
+
+        for value in [110.0, 120.0, 130.0] {
+            keeper.add(value);
+        }
+        assert_eq!(keeper.consecutive_above_upper(), 3);
+        assert_eq!(keeper.consecutive_below_lower(), 0);
+
+        // Back inside the bands resets the walk
+        keeper.add(115.0);
+        assert_eq!(keeper.consecutive_above_upper(), 0);
+
+        keeper.add(80.0);
+        assert_eq!(keeper.consecutive_below_lower(), 1);
+        keeper.reset();
+        assert_eq!(keeper.consecutive_below_lower(), 0);
+    }
+
+    #[test]
+    fn test_sample_std() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let population = BollingerBandKeeper::with_window(5, 2.0, Some(values.clone()));
+        let mut sample = BollingerBandKeeper::with_window(5, 2.0, Some(values));
+        assert!(!sample.is_sample_std());
+        sample.set_sample_std(true);
+        assert!(sample.is_sample_std());
+
+        // Squared deviations sum to 10: population variance 10 / 5, sample variance 10 / 4
+        assert!((population.std_dev - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((sample.std_dev - 2.5_f64.sqrt()).abs() < 1e-12);
+        assert!((sample.upper_band - (3.0 + 2.0 * 2.5_f64.sqrt())).abs() < 1e-12);
+        assert!((sample.lower_band - (3.0 - 2.0 * 2.5_f64.sqrt())).abs() < 1e-12);
+        assert!(sample.upper_band > population.upper_band);
+        assert!(sample.lower_band < population.lower_band);
+
+        sample.add(6.0);
+        assert!((sample.std_dev - 2.5_f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sample_std_single_value() {
+        let mut keeper = BollingerBandKeeper::with_window(5, 2.0, None);
+        keeper.set_sample_std(true);
+        keeper.add(100.0);
+        assert_eq!(keeper.std_dev, 0.0);
+        assert_eq!(keeper.upper_band, 100.0);
+    }
+
+    /// Deterministic pseudo-random values around 100 for the long-run tests
+    fn random_walk(count: usize) -> Vec<f64> {
+        let mut state: u64 = 42;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                100.0 + (state >> 11) as f64 / (1u64 << 53) as f64 * 10.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_incremental_matches_naive() {
+        let window = 200;
+        let values = random_walk(10_000);
+        let mut keeper = BollingerBandKeeper::with_window(window, 2.0, None);
+        for (i, value) in values.iter().enumerate() {
+            keeper.add(*value);
+
+            let start = (i + 1).saturating_sub(window);
+            let slice = &values[start..=i];
+            let mean = slice.iter().sum::<f64>() / slice.len() as f64;
+            let variance =
+                slice.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / slice.len() as f64;
+            let std = variance.sqrt();
+            assert!((keeper.upper_band - (mean + 2.0 * std)).abs() < 1e-9, "index {}", i);
+            assert!((keeper.lower_band - (mean - 2.0 * std)).abs() < 1e-9, "index {}", i);
+        }
+    }
+
+    /// Throughput check, run with `cargo test --release -- --ignored`. The bound is loose
+    /// enough for a debug build; re-summing a 10k window per add would take minutes.
+    #[test]
+    #[ignore]
+    fn bench_add_large_window() {
+        let values = random_walk(1_000_000);
+        let mut keeper = BollingerBandKeeper::with_window(10_000, 2.0, None);
+        let start = std::time::Instant::now();
+        for value in values {
+            keeper.add(value);
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_band_getters() {
+        let mut keeper = BollingerBandKeeper::with_window(4, 2.0, None);
+        assert_eq!(keeper.get_bandwidth(), 0.0);
+        for value in [100.0, 102.0, 98.0, 101.0] {
+            keeper.add(value);
+        }
+
+        let (upper, lower, middle) =
+            (keeper.get_upper_band(), keeper.get_lower_band(), keeper.get_middle_band());
+        assert!((middle - 100.25).abs() < 1e-12);
+        assert!(upper > middle && middle > lower);
+        assert!(!keeper.is_above_upper_band(upper));
+        assert!(keeper.is_above_upper_band(upper + 1e-9));
+        assert!(!keeper.is_below_lower_band(lower));
+        assert!(keeper.is_below_lower_band(lower - 1e-9));
+        assert!(keeper.is_inside_band(upper) && keeper.is_inside_band(lower));
+        assert!((keeper.get_bandwidth() - (upper - lower) / middle).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_percent_b() {
+        let mut keeper = BollingerBandKeeper::with_window(4, 2.0, None);
+        keeper.add(100.0);
+        assert_eq!(keeper.percent_b(100.0), None);
+
+        for value in [102.0, 98.0, 101.0] {
+            keeper.add(value);
+        }
+        let middle = keeper.get_middle_band();
+        assert!((keeper.percent_b(middle).unwrap() - 0.5).abs() < 1e-12);
+        assert!((keeper.percent_b(keeper.get_upper_band()).unwrap() - 1.0).abs() < 1e-12);
+        assert_eq!(keeper.percent_b(keeper.get_lower_band()), Some(0.0));
+        assert!(keeper.percent_b(keeper.get_lower_band() - 1.0).unwrap() < 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = BollingerBandKeeper::with_window(4, 2.0, None);
+        let (keeper, restored) =
+            assert_serde_round_trip(keeper, |keeper, _, price| keeper.add(price));
+        assert_eq!(restored.get_upper_band(), keeper.get_upper_band());
+        assert_eq!(restored.get_lower_band(), keeper.get_lower_band());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || BollingerBandKeeper::with_window(3, 2.0, None),
+            |keeper, _, price| keeper.add(price),
+            |keeper| keeper.reset(),
+            |keeper| {
+                (
+                    keeper.get_upper_band(),
+                    keeper.get_lower_band(),
+                    keeper.get_timestamp_counter(),
+                )
+            },
+        );
+    }
+
+    #[test]
+    fn test_no_drift_over_long_series() {
+        // Large level with small moves, the worst case for cancellation in running sums
+        let values: Vec<f64> = random_walk(200_000).iter().map(|v| v * 1e4).collect();
+        let window = 50;
+        let mut keeper = BollingerBandKeeper::with_window(window, 2.0, None);
+        for value in &values {
+            keeper.add(*value);
+        }
+
+        let slice = &values[values.len() - window..];
+        let mean = slice.iter().sum::<f64>() / window as f64;
+        let variance = slice.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / window as f64;
+        let std = variance.sqrt();
+        assert!((keeper.get_upper_band() - (mean + 2.0 * std)).abs() / std < 1e-9);
+        assert!((keeper.get_lower_band() - (mean - 2.0 * std)).abs() / std < 1e-9);
+    }
+}
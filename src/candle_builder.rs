@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+
+/// OHLCV bar produced by the candle builders and consumed by `OhlcIndicator::ingest_candle`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    /// Opening timestamp of the bar, 0 when the source carries no timestamps
+    pub ts: u64,
+}
+
+impl Candle {
+    fn open_at(price: f64, volume: f64) -> Self {
+        Candle {
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+            ts: 0,
+        }
+    }
+
+    fn update(&mut self, price: f64, volume: f64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += volume;
+    }
+}
+
+/// Price that drives a candle's OHLC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    Bid,
+    Ask,
+    Mid,
+    Last,
+}
+
+impl PriceSource {
+    /// Picks this source's price from a quote and the last trade price
+    pub fn pick(&self, bid: f64, ask: f64, last: f64) -> f64 {
+        match self {
+            PriceSource::Bid => bid,
+            PriceSource::Ask => ask,
+            PriceSource::Mid => (bid + ask) / 2.0,
+            PriceSource::Last => last,
+        }
+    }
+}
+
+/// Builds constant-volume bars from a trade stream
+pub struct VolumeBarBuilder {
+    volume_per_bar: f64,
+    price_source: PriceSource,
+    current: Option<Candle>,
+    completed: VecDeque<Candle>,
+}
+
+impl VolumeBarBuilder {
+    /// Creates a builder whose bars follow the trade price through `on_trade` and the
+    /// mid price through `on_tick`
+    pub fn new(volume_per_bar: f64) -> Result<Self, String> {
+        Self::with_price_source(volume_per_bar, PriceSource::Mid)
+    }
+
+    /// Creates a builder whose bars follow `price_source` when fed through `on_tick`
+    pub fn with_price_source(
+        volume_per_bar: f64,
+        price_source: PriceSource,
+    ) -> Result<Self, String> {
+        if volume_per_bar <= 0.0 {
+            return Err("Volume per bar must be positive".to_string());
+        }
+
+        Ok(VolumeBarBuilder {
+            volume_per_bar,
+            price_source,
+            current: None,
+            completed: VecDeque::new(),
+        })
+    }
+
+    /// Adds a trade; volume beyond the bar threshold is carried into the next bar(s).
+    /// Errors on a non-finite or non-positive volume.
+    pub fn on_trade(&mut self, price: f64, volume: f64) -> Result<(), String> {
+        if !volume.is_finite() || volume <= 0.0 {
+            return Err(format!("Trade volume must be positive and finite, got {}", volume));
+        }
+
+        let mut remaining = volume;
+        while remaining > 0.0 {
+            let filled = self.current.map_or(0.0, |candle| candle.volume);
+            let room = (self.volume_per_bar - filled).max(0.0);
+            let take = remaining.min(room);
+            match self.current.as_mut() {
+                Some(candle) => candle.update(price, take),
+                None => self.current = Some(Candle::open_at(price, take)),
+            }
+            remaining -= take;
+
+            // Completion follows from taking the whole room, not from re-adding the filled
+            // volume, which can round to just below the threshold and strand the remainder
+            if take == room {
+                if let Some(candle) = self.current.take() {
+                    self.completed.push_back(candle);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a trade of `volume` at `last` with the prevailing quote, recording the price
+    /// selected by the builder's price source
+    pub fn on_tick(&mut self, bid: f64, ask: f64, last: f64, volume: f64) -> Result<(), String> {
+        self.on_trade(self.price_source.pick(bid, ask, last), volume)
+    }
+
+    pub fn get_price_source(&self) -> PriceSource {
+        self.price_source
+    }
+
+    /// Pops the oldest completed bar
+    pub fn poll(&mut self) -> Option<Candle> {
+        self.completed.pop_front()
+    }
+
+    /// Bar still being filled, if any
+    pub fn get_current(&self) -> Option<Candle> {
+        self.current
+    }
+
+    pub fn get_volume_per_bar(&self) -> f64 {
+        self.volume_per_bar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_on_non_positive_volume() {
+        assert!(VolumeBarBuilder::new(0.0).is_err());
+        assert!(VolumeBarBuilder::new(-1.0).is_err());
+    }
+
+    #[test]
+    fn test_bar_with_carryover() {
+        let mut builder = VolumeBarBuilder::new(10.0).unwrap();
+        builder.on_trade(100.0, 4.0).unwrap();
+        builder.on_trade(102.0, 3.0).unwrap();
+        assert_eq!(builder.poll(), None);
+
+        builder.on_trade(99.0, 5.0).unwrap();
+        let bar = builder.poll().unwrap();
+        assert_eq!(
+            bar,
+            Candle {
+                open: 100.0,
+                high: 102.0,
+                low: 99.0,
+                close: 99.0,
+                volume: 10.0,
+                ts: 0,
+            }
+        );
+        assert_eq!(builder.poll(), None);
+
+        // The 2 units past the threshold open the next bar
+        let current = builder.get_current().unwrap();
+        assert_eq!(current.open, 99.0);
+        assert_eq!(current.volume, 2.0);
+    }
+
+    #[test]
+    fn test_large_trade_fills_several_bars() {
+        let mut builder = VolumeBarBuilder::new(10.0).unwrap();
+        builder.on_trade(100.0, 25.0).unwrap();
+        assert_eq!(builder.poll().unwrap().volume, 10.0);
+        assert_eq!(builder.poll().unwrap().volume, 10.0);
+        assert_eq!(builder.poll(), None);
+        assert_eq!(builder.get_current().unwrap().volume, 5.0);
+
+        // An exact fill leaves no partial bar behind
+        builder.on_trade(101.0, 5.0).unwrap();
+        assert_eq!(builder.poll().unwrap().close, 101.0);
+        assert_eq!(builder.get_current(), None);
+    }
+
+    #[test]
+    fn test_price_source() {
+        let mut mid_builder = VolumeBarBuilder::with_price_source(3.0, PriceSource::Mid).unwrap();
+        let mut bid_builder = VolumeBarBuilder::with_price_source(3.0, PriceSource::Bid).unwrap();
+        let ticks = [(99.0, 101.0, 100.5), (100.0, 104.0, 103.0), (98.0, 99.0, 98.5)];
+        for (bid, ask, last) in ticks {
+            mid_builder.on_tick(bid, ask, last, 1.0).unwrap();
+            bid_builder.on_tick(bid, ask, last, 1.0).unwrap();
+        }
+
+        let mid_bar = mid_builder.poll().unwrap();
+        let bid_bar = bid_builder.poll().unwrap();
+        let ohlc = |bar: Candle| (bar.open, bar.high, bar.low, bar.close);
+        assert_eq!(ohlc(mid_bar), (100.0, 102.0, 98.5, 98.5));
+        assert_eq!(ohlc(bid_bar), (99.0, 100.0, 98.0, 98.0));
+        assert_eq!(VolumeBarBuilder::new(3.0).unwrap().get_price_source(), PriceSource::Mid);
+    }
+
+    #[test]
+    fn test_default_builder_ticks_on_mid() {
+        let mut builder = VolumeBarBuilder::new(3.0).unwrap();
+        builder.on_tick(99.0, 101.0, 100.5, 1.0).unwrap();
+        builder.on_tick(100.0, 104.0, 103.0, 1.0).unwrap();
+        builder.on_tick(98.0, 99.0, 98.5, 1.0).unwrap();
+        let bar = builder.poll().unwrap();
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (100.0, 102.0, 98.5, 98.5));
+
+        // Trades still record their own price
+        builder.on_trade(105.0, 3.0).unwrap();
+        assert_eq!(builder.poll().unwrap().close, 105.0);
+    }
+
+    #[test]
+    fn test_error_on_invalid_trade_volume() {
+        let mut builder = VolumeBarBuilder::new(10.0).unwrap();
+        assert!(builder.on_trade(100.0, 0.0).is_err());
+        assert!(builder.on_trade(100.0, -1.0).is_err());
+        assert!(builder.on_trade(100.0, f64::NAN).is_err());
+        assert!(builder.on_trade(100.0, f64::INFINITY).is_err());
+        assert_eq!(builder.get_current(), None);
+    }
+
+    #[test]
+    fn test_output_volume_equals_input_volume() {
+        // Simple LCG so the trade sizes are irregular but reproducible
+        let mut state: u64 = 42;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        for volume_per_bar in [0.17, 0.3, 1.0, 7.1] {
+            let mut builder = VolumeBarBuilder::new(volume_per_bar).unwrap();
+            let mut input = 0.0;
+            for _ in 0..500 {
+                let volume = (next() * 2.0 * 100.0).round() / 100.0 + 0.01;
+                builder.on_trade(100.0 + next(), volume).unwrap();
+                input += volume;
+            }
+
+            let mut output = builder.get_current().map_or(0.0, |candle| candle.volume);
+            while let Some(candle) = builder.poll() {
+                assert!(candle.volume <= volume_per_bar + 1e-9);
+                output += candle.volume;
+            }
+            assert!(
+                (output - input).abs() < 1e-9,
+                "vpb {} in {} out {}",
+                volume_per_bar,
+                input,
+                output
+            );
+        }
+    }
+}
@@ -4,6 +4,28 @@ use crate::trade_price_keeper::TradePriceKeeper;
 pub const BUY: bool = true;
 pub const SELL: bool = false;
 
+/// Direction of a line crossing another, e.g. a fast line crossing a signal line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossSignal {
+    GoldenCross,
+    DeathCross,
+}
+
+/// Rounds `value` to the nearest multiple of `tick_size`, e.g. 0.01 for two decimals.
+/// Returns `value` unchanged when `tick_size` is not positive.
+pub fn round_to(value: f64, tick_size: f64) -> f64 {
+    if tick_size <= 0.0 {
+        return value;
+    }
+    let steps = (value / tick_size).round();
+    if tick_size < 1.0 {
+        // Dividing by the reciprocal keeps decimal ticks such as 0.01 exact, 1020 / 100 == 10.2
+        steps / (1.0 / tick_size)
+    } else {
+        steps * tick_size
+    }
+}
+
 /// Calculates the standard deviation (not variance, despite the name) of prices
 /// in the given range from the TradePriceKeeper.
 /// 
@@ -108,3 +130,20 @@ pub fn calculate_volatility_percentage(prices: &[f64], period: usize) -> Vec<Opt
 
     volatilities
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_to() {
+        assert_eq!(round_to(1.23456, 0.01), 1.23);
+        assert_eq!(round_to(1.235001, 0.01), 1.24);
+        assert_eq!(round_to(100.1, 0.25), 100.0);
+        assert_eq!(round_to(100.13, 0.25), 100.25);
+        assert_eq!(round_to(-100.38, 0.25), -100.5);
+        assert_eq!(round_to(10.2000001, 0.01), 10.2);
+        assert_eq!(round_to(1234.0, 5.0), 1235.0);
+        assert_eq!(round_to(1.23456, 0.0), 1.23456);
+    }
+}
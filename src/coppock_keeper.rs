@@ -0,0 +1,149 @@
+use std::collections::VecDeque;
+
+use crate::wma_keeper::WmaKeeper;
+
+/// Coppock curve: WMA over `wma_period` of the sum of two percent rates of change
+pub struct CoppockKeeper {
+    long_roc_period: usize,
+    short_roc_period: usize,
+    prices: VecDeque<f64>,
+    wma_keeper: WmaKeeper,
+    curve: f64,
+    prev_curve: f64,
+    ready_count: usize,
+    timestamp_counter: u64,
+}
+
+impl CoppockKeeper {
+    /// Classic monthly settings are `new(14, 11, 10)`
+    pub fn new(
+        long_roc_period: usize,
+        short_roc_period: usize,
+        wma_period: usize,
+    ) -> Result<Self, String> {
+        if long_roc_period < 1 || short_roc_period < 1 || wma_period < 1 {
+            return Err("Coppock periods at least 1".to_string());
+        }
+
+        let history_len = long_roc_period.max(short_roc_period) + 1;
+        Ok(CoppockKeeper {
+            long_roc_period,
+            short_roc_period,
+            prices: VecDeque::with_capacity(history_len + 1),
+            wma_keeper: WmaKeeper::new(wma_period),
+            curve: 0.0,
+            prev_curve: 0.0,
+            ready_count: 0,
+            timestamp_counter: 1,
+        })
+    }
+
+    /// Percent change of the latest price over `period` prices ago
+    fn roc(&self, period: usize) -> f64 {
+        let latest = self.prices[self.prices.len() - 1];
+        let base = self.prices[self.prices.len() - 1 - period];
+        if base == 0.0 {
+            return 0.0;
+        }
+        100.0 * (latest - base) / base
+    }
+
+    pub fn add(&mut self, price: f64) {
+        let history_len = self.long_roc_period.max(self.short_roc_period) + 1;
+        self.prices.push_back(price);
+        while self.prices.len() > history_len {
+            self.prices.pop_front();
+        }
+        if self.prices.len() < history_len {
+            return;
+        }
+
+        let roc_sum = self.roc(self.long_roc_period) + self.roc(self.short_roc_period);
+        self.wma_keeper.add(self.timestamp_counter, roc_sum);
+        self.timestamp_counter += 1;
+        if self.is_ready() {
+            self.prev_curve = self.curve;
+            self.curve = self.wma_keeper.get();
+            self.ready_count += 1;
+        }
+    }
+
+    /// Whether both ROCs and the full WMA window are available
+    pub fn is_ready(&self) -> bool {
+        self.wma_keeper.is_full()
+    }
+
+    /// Gets the curve, 0.0 until the keeper is ready
+    pub fn get(&self) -> f64 {
+        self.curve
+    }
+
+    /// Gets the curve before the latest value
+    pub fn get_prev(&self) -> f64 {
+        self.prev_curve
+    }
+
+    /// Whether the curve rose on the latest value
+    pub fn is_turning_up(&self) -> bool {
+        self.ready_count >= 2 && self.curve > self.prev_curve
+    }
+
+    /// Whether the curve crossed zero from below on the latest value, the classic buy signal
+    pub fn crossed_above_zero(&self) -> bool {
+        self.ready_count >= 2 && self.prev_curve < 0.0 && self.curve >= 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coppock_new() {
+        assert!(CoppockKeeper::new(14, 11, 10).is_ok());
+        assert!(CoppockKeeper::new(14, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let mut keeper = CoppockKeeper::new(3, 2, 2).unwrap();
+        for price in [100.0, 102.0, 104.0, 106.0] {
+            keeper.add(price);
+        }
+        // One ROC sum so far, the WMA needs two
+        assert!(!keeper.is_ready());
+        assert_eq!(keeper.get(), 0.0);
+
+        keeper.add(108.0);
+        assert!(keeper.is_ready());
+        let first = 100.0 * 6.0 / 100.0 + 100.0 * 4.0 / 102.0;
+        let second = 100.0 * 6.0 / 102.0 + 100.0 * 4.0 / 104.0;
+        assert!((keeper.get() - (first + 2.0 * second) / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_recovery_turns_up() {
+        let mut keeper = CoppockKeeper::new(4, 3, 3).unwrap();
+        let mut turned_up_at = None;
+        let mut crossed_at = None;
+        // Decline, then a recovery
+        let prices = [
+            120.0, 116.0, 112.0, 108.0, 104.0, 100.0, 97.0, 95.0, 94.0, 94.5, 96.0, 99.0, 103.0,
+            107.0, 111.0,
+        ];
+        for (i, price) in prices.iter().enumerate() {
+            keeper.add(*price);
+            if keeper.is_turning_up() && turned_up_at.is_none() {
+                turned_up_at = Some(i);
+            }
+            if keeper.crossed_above_zero() {
+                crossed_at = Some(i);
+            }
+        }
+
+        // The curve turns up while still negative as the decline slows, then crosses zero
+        assert_eq!(turned_up_at, Some(7));
+        assert_eq!(crossed_at, Some(11));
+        assert!(keeper.get() > 0.0);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use crate::common_utils::BUY;
+
+/// Cumulative volume delta: running buy volume minus sell volume,
+/// optionally also summed over the last `window` trades
+pub struct CumDeltaKeeper {
+    delta: f64,
+    window: usize,
+    window_deltas: VecDeque<f64>,
+    window_sum: f64,
+}
+
+impl Default for CumDeltaKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CumDeltaKeeper {
+    pub fn new() -> Self {
+        Self::with_window(0)
+    }
+
+    /// Creates a keeper that also tracks the delta of the last `window` trades (0 disables it)
+    pub fn with_window(window: usize) -> Self {
+        CumDeltaKeeper {
+            delta: 0.0,
+            window,
+            window_deltas: VecDeque::with_capacity(window + 1),
+            window_sum: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, volume: f64, side: bool) {
+        let signed = if side == BUY { volume } else { -volume };
+        self.delta += signed;
+
+        if self.window > 0 {
+            self.window_deltas.push_back(signed);
+            self.window_sum += signed;
+            while self.window_deltas.len() > self.window {
+                if let Some(remove) = self.window_deltas.pop_front() {
+                    self.window_sum -= remove;
+                }
+            }
+        }
+    }
+
+    /// Gets the running delta since construction or the last session reset
+    pub fn get(&self) -> f64 {
+        self.delta
+    }
+
+    /// Gets the delta over the last `window` trades, 0.0 when no window is configured
+    pub fn get_windowed(&self) -> f64 {
+        self.window_sum
+    }
+
+    /// Starts a new session, clearing the running and windowed deltas
+    pub fn reset_session(&mut self) {
+        self.delta = 0.0;
+        self.window_deltas.clear();
+        self.window_sum = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_utils::SELL;
+
+    #[test]
+    fn test_running_delta() {
+        let mut keeper = CumDeltaKeeper::new();
+        assert_eq!(keeper.get(), 0.0);
+
+        keeper.add(5.0, BUY);
+        keeper.add(2.0, SELL);
+        keeper.add(1.5, BUY);
+        keeper.add(4.0, SELL);
+        assert_eq!(keeper.get(), 0.5);
+        assert_eq!(keeper.get_windowed(), 0.0);
+    }
+
+    #[test]
+    fn test_windowed_delta() {
+        let mut keeper = CumDeltaKeeper::with_window(2);
+        keeper.add(5.0, BUY);
+        keeper.add(2.0, SELL);
+        keeper.add(1.0, SELL);
+        assert_eq!(keeper.get(), 2.0);
+        assert_eq!(keeper.get_windowed(), -3.0);
+    }
+
+    #[test]
+    fn test_reset_session() {
+        let mut keeper = CumDeltaKeeper::with_window(3);
+        keeper.add(5.0, BUY);
+        keeper.add(2.0, SELL);
+        keeper.reset_session();
+        assert_eq!(keeper.get(), 0.0);
+        assert_eq!(keeper.get_windowed(), 0.0);
+
+        keeper.add(1.0, SELL);
+        assert_eq!(keeper.get(), -1.0);
+        assert_eq!(keeper.get_windowed(), -1.0);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::indicator::OhlcIndicator;
+use crate::min_max_keeper::MinMaxKeeper;
+
+/// Side of the channel that price broke through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakout {
+    UpperBreakout,
+    LowerBreakout,
+}
+
+/// Donchian channel: highest high and lowest low over the last `period` bars
+pub struct DonchianChannelKeeper {
+    highs: MinMaxKeeper,
+    lows: MinMaxKeeper,
+    prev_upper: Option<f64>,
+    prev_lower: Option<f64>,
+    last_breakout: Option<Breakout>,
+}
+
+impl DonchianChannelKeeper {
+    pub fn new(period: usize) -> Result<Self, String> {
+        if period < 1 {
+            return Err("Donchian period at least 1".to_string());
+        }
+
+        Ok(DonchianChannelKeeper {
+            highs: MinMaxKeeper::fixed_window(period),
+            lows: MinMaxKeeper::fixed_window(period),
+            prev_upper: None,
+            prev_lower: None,
+            last_breakout: None,
+        })
+    }
+
+    pub fn add(&mut self, high: f64, low: f64) -> Result<(), String> {
+        if self.size() > 0 {
+            self.prev_upper = Some(self.get_upper());
+            self.prev_lower = Some(self.get_lower());
+        }
+
+        self.highs.add(high).map_err(|e| e.to_string())?;
+        self.lows.add(low).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn size(&self) -> usize {
+        self.highs.get_len()
+    }
+
+    pub fn get_upper(&self) -> f64 {
+        self.highs.get_max()
+    }
+
+    pub fn get_lower(&self) -> f64 {
+        self.lows.get_min()
+    }
+
+    pub fn get_middle(&self) -> f64 {
+        (self.get_upper() + self.get_lower()) / 2.0
+    }
+
+    /// Checks `close` against the channel as it was before the latest bar was added.
+    /// Returns a breakout once when price leaves the prior channel; repeated polls while
+    /// price stays beyond the same side return None until it comes back inside.
+    pub fn poll_breakout(&mut self, close: f64) -> Option<Breakout> {
+        let (prev_upper, prev_lower) = match (self.prev_upper, self.prev_lower) {
+            (Some(upper), Some(lower)) => (upper, lower),
+            _ => return None,
+        };
+
+        let breakout = if close > prev_upper {
+            Some(Breakout::UpperBreakout)
+        } else if close < prev_lower {
+            Some(Breakout::LowerBreakout)
+        } else {
+            None
+        };
+
+        if breakout == self.last_breakout {
+            return None;
+        }
+        self.last_breakout = breakout;
+        breakout
+    }
+}
+
+impl OhlcIndicator for DonchianChannelKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        _close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_donchian_new() {
+        assert!(DonchianChannelKeeper::new(20).is_ok());
+        assert!(DonchianChannelKeeper::new(0).is_err());
+    }
+
+    #[test]
+    fn test_channel() {
+        let mut keeper = DonchianChannelKeeper::new(3).unwrap();
+        keeper.add(101.0, 99.0).unwrap();
+        keeper.add(105.0, 100.0).unwrap();
+        keeper.add(103.0, 98.0).unwrap();
+        assert_eq!(keeper.get_upper(), 105.0);
+        assert_eq!(keeper.get_lower(), 98.0);
+        assert_eq!(keeper.get_middle(), 101.5);
+
+        // Oldest bar rolls out of the window
+        keeper.add(102.0, 101.0).unwrap();
+        assert_eq!(keeper.get_upper(), 105.0);
+        assert_eq!(keeper.get_lower(), 98.0);
+        keeper.add(102.0, 101.0).unwrap();
+        assert_eq!(keeper.get_upper(), 103.0);
+        assert_eq!(keeper.size(), 3);
+    }
+
+    #[test]
+    fn test_poll_breakout() {
+        let mut keeper = DonchianChannelKeeper::new(3).unwrap();
+        keeper.add(101.0, 99.0).unwrap();
+        assert_eq!(keeper.poll_breakout(100.0), None);
+        keeper.add(101.0, 99.0).unwrap();
+        keeper.add(101.0, 99.0).unwrap();
+        assert_eq!(keeper.poll_breakout(100.0), None);
+
+        keeper.add(103.0, 100.0).unwrap();
+        assert_eq!(keeper.poll_breakout(102.5), Some(Breakout::UpperBreakout));
+
+        // Still beyond the upper channel, no repeated signal
+        keeper.add(104.0, 101.0).unwrap();
+        assert_eq!(keeper.poll_breakout(103.5), None);
+
+        keeper.add(100.0, 95.0).unwrap();
+        assert_eq!(keeper.poll_breakout(96.0), Some(Breakout::LowerBreakout));
+    }
+}
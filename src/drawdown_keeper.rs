@@ -0,0 +1,120 @@
+use crate::min_max_keeper::MinMaxKeeper;
+
+/// Drawdown of the latest value from the highest value of the last `period` values,
+/// with an optional latching circuit breaker
+pub struct DrawdownKeeper {
+    period: usize,
+    drawdown: f64,
+    breaker_threshold: Option<f64>,
+    breaker_tripped: bool,
+    min_max_keeper: MinMaxKeeper,
+}
+
+impl DrawdownKeeper {
+    pub fn new(period: usize) -> Result<Self, String> {
+        Self::build(period, None)
+    }
+
+    /// Creates a keeper whose breaker trips once the drawdown exceeds `max_dd`
+    /// and stays tripped until `reset_breaker`
+    pub fn with_breaker(period: usize, max_dd: f64) -> Result<Self, String> {
+        Self::build(period, Some(max_dd))
+    }
+
+    fn build(period: usize, breaker_threshold: Option<f64>) -> Result<Self, String> {
+        if period < 1 {
+            return Err("Drawdown period at least 1".to_string());
+        }
+
+        Ok(DrawdownKeeper {
+            period,
+            drawdown: 0.0,
+            breaker_threshold,
+            breaker_tripped: false,
+            min_max_keeper: MinMaxKeeper::fixed_window(period),
+        })
+    }
+
+    pub fn add(&mut self, value: f64) -> Result<(), String> {
+        self.min_max_keeper.add(value).map_err(|e| e.to_string())?;
+        let peak = self.min_max_keeper.get_max();
+        self.drawdown = if peak > 0.0 { (peak - value) / peak } else { 0.0 };
+
+        if let Some(max_dd) = self.breaker_threshold {
+            if self.is_breached(max_dd) {
+                self.breaker_tripped = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gets the drawdown as a fraction of the rolling peak, 0.0 at the peak
+    pub fn get_drawdown(&self) -> f64 {
+        self.drawdown
+    }
+
+    pub fn get_period(&self) -> usize {
+        self.period
+    }
+
+    /// Whether the current drawdown exceeds `max_dd`
+    pub fn is_breached(&self, max_dd: f64) -> bool {
+        self.drawdown > max_dd
+    }
+
+    /// Whether the breaker has tripped since the last `reset_breaker`
+    pub fn breaker_tripped(&self) -> bool {
+        self.breaker_tripped
+    }
+
+    pub fn reset_breaker(&mut self) {
+        self.breaker_tripped = false;
+    }
+
+    /// Clears the window, drawdown and breaker, keeping the period and threshold
+    pub fn reset(&mut self) {
+        self.min_max_keeper.reset();
+        self.drawdown = 0.0;
+        self.breaker_tripped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_drawdown() {
+        let mut keeper = DrawdownKeeper::new(3).unwrap();
+        assert!(DrawdownKeeper::new(0).is_err());
+        keeper.add(100.0).unwrap();
+        keeper.add(90.0).unwrap();
+        assert!((keeper.get_drawdown() - 0.1).abs() < 1e-12);
+        keeper.add(95.0).unwrap();
+        // The 100.0 peak leaves the window
+        keeper.add(95.0).unwrap();
+        assert_eq!(keeper.get_drawdown(), 0.0);
+    }
+
+    #[test]
+    fn test_breaker_latches_until_reset() {
+        let mut keeper = DrawdownKeeper::with_breaker(10, 0.05).unwrap();
+        keeper.add(100.0).unwrap();
+        keeper.add(97.0).unwrap();
+        assert!(!keeper.is_breached(0.05));
+        assert!(!keeper.breaker_tripped());
+
+        keeper.add(94.0).unwrap();
+        assert!(keeper.is_breached(0.05));
+        assert!(keeper.breaker_tripped());
+
+        // Recovery clears the breach but not the latch
+        keeper.add(100.0).unwrap();
+        assert!(!keeper.is_breached(0.05));
+        assert!(keeper.breaker_tripped());
+
+        keeper.reset_breaker();
+        assert!(!keeper.breaker_tripped());
+    }
+}
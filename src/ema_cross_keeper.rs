@@ -0,0 +1,113 @@
+use crate::common_utils::CrossSignal;
+use crate::ema_keeper::EmaKeeper;
+
+/// Fast and slow EMAs of the same price series with golden/death cross detection
+pub struct EmaCrossKeeper {
+    fast: EmaKeeper,
+    slow: EmaKeeper,
+    timestamp_counter: u64,
+    prev_fast_above: Option<bool>,
+}
+
+impl EmaCrossKeeper {
+    pub fn new(fast_period: usize, slow_period: usize) -> Result<Self, String> {
+        if fast_period < 1 {
+            return Err("EMA cross fast period at least 1".to_string());
+        }
+        if slow_period <= fast_period {
+            return Err("EMA cross slow period must exceed fast period".to_string());
+        }
+
+        Ok(EmaCrossKeeper {
+            fast: EmaKeeper::new(fast_period),
+            slow: EmaKeeper::new(slow_period),
+            timestamp_counter: 1,
+            prev_fast_above: None,
+        })
+    }
+
+    pub fn add(&mut self, price: f64) {
+        self.fast.add(self.timestamp_counter, price);
+        self.slow.add(self.timestamp_counter, price);
+        self.timestamp_counter += 1;
+    }
+
+    pub fn get_fast(&self) -> f64 {
+        self.fast.get()
+    }
+
+    pub fn get_slow(&self) -> f64 {
+        self.slow.get()
+    }
+
+    /// Checks whether the fast EMA crossed the slow EMA since the last poll.
+    /// Returns the signal once per crossing; equal EMAs keep the previous side.
+    pub fn poll_cross(&mut self) -> Option<CrossSignal> {
+        let (fast, slow) = (self.fast.get(), self.slow.get());
+        if self.fast.size() == 0 || fast == slow {
+            return None;
+        }
+
+        let above = fast > slow;
+        match self.prev_fast_above.replace(above) {
+            Some(false) if above => Some(CrossSignal::GoldenCross),
+            Some(true) if !above => Some(CrossSignal::DeathCross),
+            _ => None,
+        }
+    }
+
+    /// Normalized gap `(fast - slow) / slow` at the current bar, positive with the fast EMA
+    /// above. Returns 0.0 while the slow EMA is zero.
+    pub fn cross_strength(&self) -> f64 {
+        let slow = self.slow.get();
+        if slow == 0.0 {
+            return 0.0;
+        }
+        (self.fast.get() - slow) / slow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ema_cross_new() {
+        assert!(EmaCrossKeeper::new(5, 20).is_ok());
+        assert!(EmaCrossKeeper::new(0, 20).is_err());
+        assert!(EmaCrossKeeper::new(20, 5).is_err());
+    }
+
+    #[test]
+    fn test_poll_cross() {
+        let mut keeper = EmaCrossKeeper::new(2, 5).unwrap();
+        let mut signals = Vec::new();
+        for price in [110.0, 106.0, 102.0, 98.0, 104.0, 110.0, 102.0, 94.0] {
+            keeper.add(price);
+            signals.push(keeper.poll_cross());
+        }
+        assert_eq!(signals[5], Some(CrossSignal::GoldenCross));
+        assert_eq!(signals[6], Some(CrossSignal::DeathCross));
+        assert_eq!(signals.iter().flatten().count(), 2);
+    }
+
+    #[test]
+    fn test_cross_strength() {
+        let keeper = EmaCrossKeeper::new(2, 5).unwrap();
+        assert_eq!(keeper.cross_strength(), 0.0);
+
+        let mut near_touch = EmaCrossKeeper::new(2, 5).unwrap();
+        let mut decisive = EmaCrossKeeper::new(2, 5).unwrap();
+        for price in [100.0, 99.0, 98.0] {
+            near_touch.add(price);
+            decisive.add(price);
+        }
+        near_touch.add(100.0);
+        decisive.add(120.0);
+
+        assert!(near_touch.cross_strength() > 0.0);
+        assert!(decisive.cross_strength() > 10.0 * near_touch.cross_strength());
+        let expected = (decisive.get_fast() - decisive.get_slow()) / decisive.get_slow();
+        assert_eq!(decisive.cross_strength(), expected);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::common_utils::round_to;
+use crate::indicator::Indicator;
+
+/// Exponential moving average with `alpha = 2 / (period + 1)`, seeded by the first value
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmaKeeper {
+    period: usize,
+    alpha: f64,
+    ema: f64,
+    prev_ema: f64,
+    count: usize,
+    pub prev_timestamp: u64,
+}
+
+impl EmaKeeper {
+    pub fn new(period: usize) -> Self {
+        EmaKeeper {
+            period,
+            alpha: 2.0 / (period as f64 + 1.0),
+            ema: 0.0,
+            prev_ema: 0.0,
+            count: 0,
+            prev_timestamp: 0,
+        }
+    }
+
+    pub fn get_period(&self) -> usize {
+        self.period
+    }
+
+    /// Changes the period for future updates. The current EMA is kept, so the series stays
+    /// continuous and only its responsiveness changes.
+    pub fn set_period(&mut self, new_period: usize) {
+        self.period = new_period;
+        self.alpha = 2.0 / (new_period as f64 + 1.0);
+    }
+
+    /// Number of values added, capped at the period
+    pub fn size(&self) -> usize {
+        self.count.min(self.period)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count >= self.period
+    }
+
+    /// Adds a new value, updating the EMA. The timestamp is recorded for parity with
+    /// SmaKeeper but does not gate updates.
+    pub fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        self.prev_timestamp = timestamp;
+        self.prev_ema = self.ema;
+        self.ema = if self.count == 0 {
+            value
+        } else {
+            self.alpha * value + (1.0 - self.alpha) * self.ema
+        };
+        self.count += 1;
+        self.ema
+    }
+
+    /// Gets the current EMA value
+    pub fn get(&self) -> f64 {
+        self.ema
+    }
+
+    /// Gets the current EMA rounded to the nearest multiple of `tick_size`
+    pub fn get_rounded(&self, tick_size: f64) -> f64 {
+        round_to(self.get(), tick_size)
+    }
+
+    /// Gets the previous EMA value
+    pub fn get_prev(&self) -> f64 {
+        self.prev_ema
+    }
+
+    /// Clears the EMA state, keeping the configured period
+    pub fn reset(&mut self) {
+        self.ema = 0.0;
+        self.prev_ema = 0.0;
+        self.count = 0;
+        self.prev_timestamp = 0;
+    }
+}
+
+impl Indicator for EmaKeeper {
+    fn update(&mut self, value: f64) -> f64 {
+        let timestamp = self.prev_timestamp + 1;
+        self.add(timestamp, value)
+    }
+
+    fn value(&self) -> f64 {
+        self.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_by_first_value() {
+        let mut keeper = EmaKeeper::new(3);
+        assert_eq!(keeper.add(1, 10.0), 10.0);
+        assert_eq!(keeper.get_prev(), 0.0);
+    }
+
+    #[test]
+    fn test_recurrence() {
+        let mut keeper = EmaKeeper::new(3);
+        // alpha = 2 / (3 + 1) = 0.5
+        keeper.add(1, 10.0);
+        assert_eq!(keeper.add(2, 20.0), 15.0);
+        assert_eq!(keeper.add(3, 11.0), 13.0);
+        assert_eq!(keeper.get_prev(), 15.0);
+        assert!(keeper.is_full());
+        assert_eq!(keeper.size(), 3);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut keeper = EmaKeeper::new(3);
+        keeper.add(1, 10.0);
+        keeper.add(2, 20.0);
+        keeper.reset();
+        assert_eq!(keeper.size(), 0);
+        assert_eq!(keeper.add(1, 30.0), 30.0);
+    }
+
+    #[test]
+    fn test_set_period_keeps_value() {
+        let mut keeper = EmaKeeper::new(3);
+        keeper.add(1, 10.0);
+        keeper.add(2, 20.0);
+        let before = keeper.get();
+
+        keeper.set_period(9);
+        assert_eq!(keeper.get_period(), 9);
+        assert_eq!(keeper.get(), before);
+        // alpha = 2 / (9 + 1) = 0.2
+        assert_eq!(keeper.add(3, 25.0), 0.2 * 25.0 + 0.8 * before);
+        assert_eq!(keeper.get_prev(), before);
+    }
+}
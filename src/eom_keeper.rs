@@ -0,0 +1,87 @@
+use crate::sma_keeper::SmaKeeper;
+
+/// Ease of Movement `(mid - prev_mid) / (volume / (high - low))`, SMA-smoothed
+pub struct EomKeeper {
+    sma_keeper: SmaKeeper,
+    prev_mid: Option<f64>,
+    raw: f64,
+    timestamp_counter: u64,
+}
+
+impl EomKeeper {
+    /// Creates a keeper smoothing the one-bar EOM with an SMA over `period`, e.g. 14
+    pub fn new(period: usize) -> Result<Self, String> {
+        if period < 1 {
+            return Err("EOM period at least 1".to_string());
+        }
+
+        Ok(EomKeeper {
+            sma_keeper: SmaKeeper::new(period, 0, 0.0),
+            prev_mid: None,
+            raw: 0.0,
+            timestamp_counter: 1,
+        })
+    }
+
+    /// Adds a bar and returns the smoothed EOM. The first bar only sets the midpoint.
+    /// A bar with zero volume or `high == low` has a one-bar EOM of 0.0.
+    pub fn add(&mut self, high: f64, low: f64, volume: f64) -> f64 {
+        let mid = (high + low) / 2.0;
+        let prev_mid = match self.prev_mid.replace(mid) {
+            Some(prev_mid) => prev_mid,
+            None => return self.get(),
+        };
+
+        let range = high - low;
+        self.raw = if volume.abs() > 1e-10 && range.abs() > 1e-10 {
+            (mid - prev_mid) / (volume / range)
+        } else {
+            0.0
+        };
+        self.sma_keeper.add(self.timestamp_counter, self.raw);
+        self.timestamp_counter += 1;
+        self.get()
+    }
+
+    /// Gets the smoothed EOM
+    pub fn get(&self) -> f64 {
+        self.sma_keeper.get()
+    }
+
+    /// Gets the latest unsmoothed EOM
+    pub fn get_raw(&self) -> f64 {
+        self.raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_easy_up_move_on_low_volume() {
+        let mut light = EomKeeper::new(2).unwrap();
+        let mut heavy = EomKeeper::new(2).unwrap();
+        // Midpoints 9.5, 10.5, 11.5 with a one-point range each bar
+        for (high, low) in [(10.0, 9.0), (11.0, 10.0), (12.0, 11.0)] {
+            light.add(high, low, 10.0);
+            heavy.add(high, low, 10_000.0);
+        }
+        assert!((light.get() - 0.1).abs() < 1e-12);
+        assert!((heavy.get() - 0.0001).abs() < 1e-12);
+        assert!(light.get() > 100.0 * heavy.get());
+    }
+
+    #[test]
+    fn test_zero_volume_and_flat_bar() {
+        assert!(EomKeeper::new(0).is_err());
+
+        let mut keeper = EomKeeper::new(3).unwrap();
+        assert_eq!(keeper.add(10.0, 9.0, 100.0), 0.0);
+        keeper.add(11.0, 10.0, 0.0);
+        assert_eq!(keeper.get_raw(), 0.0);
+        keeper.add(10.5, 10.5, 100.0);
+        assert_eq!(keeper.get_raw(), 0.0);
+        assert!(keeper.get().is_finite());
+    }
+}
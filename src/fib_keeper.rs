@@ -0,0 +1,66 @@
+/// Standard Fibonacci retracement ratios
+pub const FIB_RATIOS: [f64; 5] = [0.236, 0.382, 0.5, 0.618, 0.786];
+
+/// Fibonacci retracement levels of the move from a swing high down to a swing low
+pub struct FibKeeper {
+    swing_high: f64,
+    swing_low: f64,
+}
+
+impl Default for FibKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FibKeeper {
+    pub fn new() -> Self {
+        FibKeeper {
+            swing_high: 0.0,
+            swing_low: 0.0,
+        }
+    }
+
+    pub fn set_swing(&mut self, high: f64, low: f64) {
+        self.swing_high = high;
+        self.swing_low = low;
+    }
+
+    pub fn get_swing(&self) -> (f64, f64) {
+        (self.swing_high, self.swing_low)
+    }
+
+    /// Price retracing `ratio` of the swing back from the high: 0.0 is the high, 1.0 the low
+    pub fn level(&self, ratio: f64) -> f64 {
+        self.swing_high - (self.swing_high - self.swing_low) * ratio
+    }
+
+    /// (ratio, price) for each of the standard `FIB_RATIOS`
+    pub fn all_levels(&self) -> Vec<(f64, f64)> {
+        FIB_RATIOS.iter().map(|ratio| (*ratio, self.level(*ratio))).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_ratio_level() {
+        let mut keeper = FibKeeper::new();
+        keeper.set_swing(200.0, 100.0);
+        assert!((keeper.level(0.618) - 138.2).abs() < 1e-9);
+        assert_eq!(keeper.level(0.0), 200.0);
+        assert_eq!(keeper.level(1.0), 100.0);
+    }
+
+    #[test]
+    fn test_all_levels() {
+        let mut keeper = FibKeeper::new();
+        keeper.set_swing(150.0, 50.0);
+        let levels = keeper.all_levels();
+        assert_eq!(levels.len(), FIB_RATIOS.len());
+        assert_eq!(levels[2], (0.5, 100.0));
+        assert!(levels.windows(2).all(|pair| pair[0].1 > pair[1].1));
+    }
+}
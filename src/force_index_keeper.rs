@@ -0,0 +1,96 @@
+use crate::ema_keeper::EmaKeeper;
+
+/// Elder's Force Index `(close - prev_close) * volume`, optionally EMA-smoothed
+pub struct ForceIndexKeeper {
+    prev_close: Option<f64>,
+    raw: f64,
+    ema: Option<EmaKeeper>,
+    timestamp_counter: u64,
+}
+
+impl Default for ForceIndexKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForceIndexKeeper {
+    /// Creates a keeper returning the raw one-bar force index
+    pub fn new() -> Self {
+        ForceIndexKeeper {
+            prev_close: None,
+            raw: 0.0,
+            ema: None,
+            timestamp_counter: 1,
+        }
+    }
+
+    /// Creates a keeper returning an EMA over `period` of the force index, e.g. 13
+    pub fn with_smoothing(period: usize) -> Result<Self, String> {
+        if period < 1 {
+            return Err("Force index smoothing period at least 1".to_string());
+        }
+
+        let mut keeper = Self::new();
+        keeper.ema = Some(EmaKeeper::new(period));
+        Ok(keeper)
+    }
+
+    /// Adds a bar. The first bar has no previous close, so its force index is 0.0
+    /// and it is not fed to the smoothing EMA.
+    pub fn add(&mut self, close: f64, volume: f64) {
+        let prev_close = match self.prev_close.replace(close) {
+            Some(prev_close) => prev_close,
+            None => return,
+        };
+
+        self.raw = (close - prev_close) * volume;
+        if let Some(ema) = self.ema.as_mut() {
+            ema.add(self.timestamp_counter, self.raw);
+        }
+        self.timestamp_counter += 1;
+    }
+
+    /// Gets the force index, smoothed when a smoothing period is set
+    pub fn get(&self) -> f64 {
+        match self.ema.as_ref() {
+            Some(ema) => ema.get(),
+            None => self.raw,
+        }
+    }
+
+    /// Gets the latest unsmoothed force index
+    pub fn get_raw(&self) -> f64 {
+        self.raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_bar_is_zero() {
+        let mut keeper = ForceIndexKeeper::new();
+        keeper.add(100.0, 1000.0);
+        assert_eq!(keeper.get(), 0.0);
+        assert!(ForceIndexKeeper::with_smoothing(0).is_err());
+    }
+
+    #[test]
+    fn test_strong_up_close_on_high_volume() {
+        let mut raw = ForceIndexKeeper::new();
+        let mut smoothed = ForceIndexKeeper::with_smoothing(3).unwrap();
+        let bars = [(100.0, 500.0), (100.5, 400.0), (100.2, 450.0), (104.0, 5000.0)];
+        for (close, volume) in bars {
+            raw.add(close, volume);
+            smoothed.add(close, volume);
+        }
+        assert!((raw.get() - 3.8 * 5000.0).abs() < 1e-9);
+        // EMA(3) of 200, -135, 19000 with alpha 0.5
+        let expected = 0.5 * 19000.0 + 0.5 * (0.5 * -135.0 + 0.5 * 200.0);
+        assert!((smoothed.get() - expected).abs() < 1e-6);
+        assert!(smoothed.get() > 9000.0);
+        assert_eq!(smoothed.get_raw(), raw.get());
+    }
+}
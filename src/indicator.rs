@@ -0,0 +1,62 @@
+use crate::candle_builder::Candle;
+
+/// A single-input streaming indicator: each value fed in updates one output value
+pub trait Indicator {
+    /// Feeds the next value and returns the updated output
+    fn update(&mut self, value: f64) -> f64;
+
+    /// Gets the current output without feeding a value
+    fn value(&self) -> f64;
+}
+
+/// A bar-fed indicator: each OHLCV bar updates its state. Keepers take only the fields they use.
+pub trait OhlcIndicator {
+    /// Feeds the next bar's high, low, close, volume and timestamp
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: f64,
+        ts: u64,
+    ) -> Result<(), String>;
+
+    /// Feeds a whole candle, so every bar-fed keeper consumes the same input type
+    fn ingest_candle(&mut self, candle: &Candle) -> Result<(), String> {
+        self.update_ohlcv(candle.high, candle.low, candle.close, candle.volume, candle.ts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atr_keeper::AtrKeeper;
+    use crate::vwap_keeper::VwapKeeper;
+
+    #[test]
+    fn test_ingest_candle_matches_direct_calls() {
+        let candles = [
+            Candle { open: 100.0, high: 102.0, low: 99.0, close: 101.0, volume: 5.0, ts: 1000 },
+            Candle { open: 101.0, high: 104.0, low: 100.0, close: 103.0, volume: 2.0, ts: 2000 },
+            Candle { open: 103.0, high: 103.5, low: 98.0, close: 99.0, volume: 8.0, ts: 3000 },
+        ];
+
+        let mut atr = AtrKeeper::new(2, 60).unwrap();
+        let mut vwap = VwapKeeper::new();
+        let mut direct_atr = AtrKeeper::new(2, 60).unwrap();
+        let mut direct_vwap = VwapKeeper::new();
+        for candle in &candles {
+            atr.ingest_candle(candle).unwrap();
+            vwap.ingest_candle(candle).unwrap();
+            direct_atr.add(candle.high, candle.low, candle.close);
+            let typical = (candle.high + candle.low + candle.close) / 3.0;
+            direct_vwap.add(typical, candle.volume, candle.ts);
+        }
+
+        assert!(atr.get() > 0.0);
+        assert_eq!(atr.get(), direct_atr.get());
+        assert!(vwap.get() > 0.0);
+        assert_eq!(vwap.get(), direct_vwap.get());
+        assert_eq!(vwap.get_last_timestamp(), 3000);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::indicator::Indicator;
+
+/// Wraps an `Indicator` and memoizes its output per timestamp, so several consumers can
+/// feed the same input at the same timestamp while the indicator updates only once
+pub struct IndicatorCache<I: Indicator> {
+    inner: I,
+    last_timestamp: Option<u64>,
+    output: f64,
+}
+
+impl<I: Indicator> IndicatorCache<I> {
+    pub fn new(inner: I) -> Self {
+        IndicatorCache {
+            inner,
+            last_timestamp: None,
+            output: 0.0,
+        }
+    }
+
+    /// Feeds `value` at `timestamp` and returns the output. A repeat of the last timestamp
+    /// returns the cached output without updating the indicator, even if `value` differs.
+    pub fn update(&mut self, timestamp: u64, value: f64) -> f64 {
+        if self.last_timestamp != Some(timestamp) {
+            self.output = self.inner.update(value);
+            self.last_timestamp = Some(timestamp);
+        }
+        self.output
+    }
+
+    /// Gets the cached output, or the indicator's current value before the first update
+    pub fn get(&self) -> f64 {
+        match self.last_timestamp {
+            Some(_) => self.output,
+            None => self.inner.value(),
+        }
+    }
+
+    /// Gets the timestamp of the cached output, None before the first update
+    pub fn get_last_timestamp(&self) -> Option<u64> {
+        self.last_timestamp
+    }
+
+    /// Gets the wrapped indicator
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    /// Unwraps the cache, returning the indicator
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sma_keeper::SmaKeeper;
+
+    /// Running sum that counts how often it is updated
+    struct CountingSum {
+        sum: f64,
+        updates: usize,
+    }
+
+    impl Indicator for CountingSum {
+        fn update(&mut self, value: f64) -> f64 {
+            self.updates += 1;
+            self.sum += value;
+            self.sum
+        }
+
+        fn value(&self) -> f64 {
+            self.sum
+        }
+    }
+
+    #[test]
+    fn test_same_timestamp_computes_once() {
+        let mut cache = IndicatorCache::new(CountingSum { sum: 0.0, updates: 0 });
+        assert_eq!(cache.get_last_timestamp(), None);
+
+        assert_eq!(cache.update(1000, 5.0), 5.0);
+        assert_eq!(cache.update(1000, 5.0), 5.0);
+        assert_eq!(cache.inner().updates, 1);
+
+        assert_eq!(cache.update(2000, 3.0), 8.0);
+        assert_eq!(cache.update(2000, 3.0), 8.0);
+        assert_eq!(cache.get(), 8.0);
+        assert_eq!(cache.get_last_timestamp(), Some(2000));
+        assert_eq!(cache.into_inner().updates, 2);
+    }
+
+    #[test]
+    fn test_wraps_keeper() {
+        let mut cache = IndicatorCache::new(SmaKeeper::new(3, 0, 0.0));
+        for (ts, value) in [(1, 10.0), (2, 20.0), (2, 20.0), (3, 40.0)] {
+            cache.update(ts, value);
+        }
+        // A second feed at ts 2 would have pushed 10.0 out of the 3-value window
+        assert_eq!(cache.get(), 70.0 / 3.0);
+        assert_eq!(cache.inner().size(), 3);
+    }
+}
@@ -1,41 +1,123 @@
+use crate::indicator::OhlcIndicator;
 use crate::min_max_keeper::MinMaxKeeper;
-use crate::sma_keeper::SmaKeeper;
+use crate::smoothing::{MovingAverage, Smoothing, SmoothingKeeper};
 
+/// Price compared against the rolling high/low window when computing %K
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KdjInputMode {
+    #[default]
+    Close,
+    /// Typical price `(high + low + close) / 3`
+    Typical,
+}
+
+impl KdjInputMode {
+    pub fn price(&self, high: f64, low: f64, close: f64) -> f64 {
+        match self {
+            KdjInputMode::Close => close,
+            KdjInputMode::Typical => (high + low + close) / 3.0,
+        }
+    }
+}
+
+/// Construction parameters of a `KdjKeeper`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KdjParams {
+    pub period_fast_k: usize,
+    pub period_slow_k: usize,
+    pub period_slow_d: usize,
+    pub input_mode: KdjInputMode,
+    pub smoothing: Smoothing,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KdjKeeper {
     period_fast_k: usize,
     period_slow_k: usize,
     period_slow_d: usize,
     min_max_keeper: MinMaxKeeper,
-    slow_k: SmaKeeper,
-    slow_d: SmaKeeper,
+    slow_k: SmoothingKeeper,
+    slow_d: SmoothingKeeper,
     j: f64,
+    prev_j: f64,
     timestamp_counter: u64,
+    input_mode: KdjInputMode,
+    smoothing: Smoothing,
 }
 
 impl KdjKeeper {
-    pub fn new(period_fast_k: usize, period_slow_k: usize, period_slow_d: usize) -> Self {
-        KdjKeeper {
+    pub fn new(period_fast_k: usize, period_slow_k: usize, period_slow_d: usize) -> Result<Self, String> {
+        Self::with_input_mode(period_fast_k, period_slow_k, period_slow_d, KdjInputMode::Close)
+    }
+
+    pub fn with_input_mode(
+        period_fast_k: usize,
+        period_slow_k: usize,
+        period_slow_d: usize,
+        input_mode: KdjInputMode,
+    ) -> Result<Self, String> {
+        Self::build(period_fast_k, period_slow_k, period_slow_d, input_mode, Smoothing::Sma)
+    }
+
+    /// Creates a keeper whose slow K and D stages use the given moving average type
+    pub fn with_smoothing(
+        period_fast_k: usize,
+        period_slow_k: usize,
+        period_slow_d: usize,
+        smoothing: Smoothing,
+    ) -> Result<Self, String> {
+        Self::build(period_fast_k, period_slow_k, period_slow_d, KdjInputMode::Close, smoothing)
+    }
+
+    pub fn from_params(params: KdjParams) -> Result<Self, String> {
+        Self::build(
+            params.period_fast_k,
+            params.period_slow_k,
+            params.period_slow_d,
+            params.input_mode,
+            params.smoothing,
+        )
+    }
+
+    fn build(
+        period_fast_k: usize,
+        period_slow_k: usize,
+        period_slow_d: usize,
+        input_mode: KdjInputMode,
+        smoothing: Smoothing,
+    ) -> Result<Self, String> {
+        if period_fast_k < 1 || period_slow_k < 1 || period_slow_d < 1 {
+            return Err("KDJ periods at least 1".to_string());
+        }
+
+        Ok(KdjKeeper {
             period_fast_k,
             period_slow_k,
             period_slow_d,
-            slow_k: SmaKeeper::new(period_slow_k, 0, 0.0),
-            slow_d: SmaKeeper::new(period_slow_d, 0, 0.0),
+            slow_k: smoothing.keeper(period_slow_k),
+            slow_d: smoothing.keeper(period_slow_d),
             min_max_keeper: MinMaxKeeper::with_capacity(period_fast_k * 2, 0.0001),
             j: 0.0,
+            prev_j: 0.0,
             timestamp_counter: 1,
-        }
+            input_mode,
+            smoothing,
+        })
     }
 
     pub fn add(&mut self, high: f64, low: f64, close: f64) -> Result<(), String> {
         self.min_max_keeper.add(high).map_err(|e| e.to_string())?;
         self.min_max_keeper.add(low).map_err(|e| e.to_string())?;
 
-        let k_fast = self.peek_next(close);
+        let k_fast = self.peek_next(self.input_mode.price(high, low, close));
         self.slow_k.add(self.timestamp_counter, k_fast);
         self.slow_d.add(self.timestamp_counter, self.slow_k.get());
         self.timestamp_counter += 1;
         let k = self.slow_k.get();
         let d = self.slow_d.get();
+        self.prev_j = self.j;
         self.j = 3.0 * k - 2.0 * d;
 
         if self.j.is_nan() {
@@ -45,13 +127,15 @@ impl KdjKeeper {
         Ok(())
     }
 
-    pub fn peek_next(&self, close: f64) -> f64 {
+    /// Fast %K of `price` against the rolling high/low window.
+    /// `add` passes the price selected by the keeper's input mode.
+    pub fn peek_next(&self, price: f64) -> f64 {
         let rolling_high = self.min_max_keeper.get_max();
         let rolling_low = self.min_max_keeper.get_min();
         if rolling_high == rolling_low {
             return 0.0;
         }
-        (100.0 * (close - rolling_low)) / (rolling_high - rolling_low)
+        (100.0 * (price - rolling_low)) / (rolling_high - rolling_low)
     }
 
     pub fn get_j_centered(&self) -> f64 {
@@ -62,17 +146,63 @@ impl KdjKeeper {
         (self.slow_k.get(), self.slow_d.get(), self.j)
     }
 
+    /// Gets (K, D, J) each clamped to [0, 100], for consumers that assume a bounded oscillator.
+    /// `get` stays unclamped.
+    pub fn get_clamped(&self) -> (f64, f64, f64) {
+        let (k, d, j) = self.get();
+        (k.clamp(0.0, 100.0), d.clamp(0.0, 100.0), j.clamp(0.0, 100.0))
+    }
+
+    /// Gets the (K, D, J) values before the latest bar
+    pub fn get_prev(&self) -> (f64, f64, f64) {
+        (self.slow_k.get_prev(), self.slow_d.get_prev(), self.prev_j)
+    }
+
     pub fn length(&self) -> usize {
         self.min_max_keeper.get_len()
     }
 
+    pub fn params(&self) -> KdjParams {
+        KdjParams {
+            period_fast_k: self.period_fast_k,
+            period_slow_k: self.period_slow_k,
+            period_slow_d: self.period_slow_d,
+            input_mode: self.input_mode,
+            smoothing: self.smoothing,
+        }
+    }
+
+    pub fn get_input_mode(&self) -> KdjInputMode {
+        self.input_mode
+    }
+
+    pub fn get_smoothing(&self) -> Smoothing {
+        self.smoothing
+    }
+
+    pub fn get_timestamp_counter(&self) -> u64 {
+        self.timestamp_counter
+    }
+
+    /// Clears the rolling window and K/D/J state, keeping the configured periods
+    pub fn reset(&mut self) {
+        self.min_max_keeper.reset();
+        self.slow_k.reset();
+        self.slow_d.reset();
+        self.j = 0.0;
+        self.prev_j = 0.0;
+        self.timestamp_counter = 1;
+    }
+
+    /// Returns 1.0 when D is above `over_bought_thresh`, -1.0 when below `over_sold_thresh`,
+    /// and exactly 0.0 otherwise or before any data
     pub fn is_over_bought_sold(
         &self,
         over_bought_thresh: f64,
         over_sold_thresh: f64,
     ) -> f64 {
         if self.slow_k.size() == 0 {
-            return 1e-6;
+            return 0.0;
         }
         let d = self.slow_d.get();
         if d > over_bought_thresh {
@@ -81,16 +211,18 @@ impl KdjKeeper {
         if d < over_sold_thresh {
             return -1.0;
         }
-        1e-6
+        0.0
     }
 
+    /// Returns 1.0 on a golden cross (K above D) at or below `cross_golden_thresh`, -1.0 on a
+    /// death cross at or above `cross_death_thresh`, and exactly 0.0 otherwise
     pub fn is_cross_golden_death(
         &self,
         cross_golden_thresh: f64,
         cross_death_thresh: f64,
     ) -> f64 {
         if self.slow_k.size() < 2 {
-            return 1e-6;
+            return 0.0;
         }
         let k = self.slow_k.get();
         let d = self.slow_d.get();
@@ -103,12 +235,14 @@ impl KdjKeeper {
         if k < d && k_prev > d_prev && k >= cross_death_thresh {
             return -1.0;
         }
-        1e-6
+        0.0
     }
 
+    /// Returns 1.0 when J is above `peak_thresh`, -1.0 when below `bottom_thresh`,
+    /// and exactly 0.0 otherwise or before any data
     pub fn is_peak_bottom(&self, peak_thresh: f64, bottom_thresh: f64) -> f64 {
         if self.slow_k.size() == 0 {
-            return 1e-6;
+            return 0.0;
         }
         if self.j > peak_thresh {
             return 1.0;
@@ -116,17 +250,33 @@ impl KdjKeeper {
         if self.j < bottom_thresh {
             return -1.0;
         }
-        1e-6
+        0.0
+    }
+}
+
+impl OhlcIndicator for KdjKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low, close)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_kdj_new() {
-        let keeper = KdjKeeper::new(9, 3, 3);
+        let keeper = KdjKeeper::new(9, 3, 3).unwrap();
         let (k, d, j) = keeper.get();
         assert_eq!(k, 0.0);
         assert_eq!(d, 0.0);
@@ -135,17 +285,17 @@ mod tests {
 
     #[test]
     fn test_peek_next() {
-        let mut keeper = KdjKeeper::new(9, 3, 3);
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
         keeper.add(110.0, 100.0, 105.0).unwrap();
         keeper.add(115.0, 105.0, 110.0).unwrap();
 
         let k_fast = keeper.peek_next(112.0);
-        assert!(k_fast >= 0.0 && k_fast <= 100.0);
+        assert!((0.0..=100.0).contains(&k_fast));
     }
 
     #[test]
     fn test_add() {
-        let mut keeper = KdjKeeper::new(9, 3, 3);
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
         keeper.add(110.0, 100.0, 105.0).unwrap();
         keeper.add(115.0, 105.0, 110.0).unwrap();
 
@@ -157,7 +307,7 @@ mod tests {
 
     #[test]
     fn test_get_j_centered() {
-        let mut keeper = KdjKeeper::new(9, 3, 3);
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
         keeper.add(110.0, 100.0, 105.0).unwrap();
         keeper.add(115.0, 105.0, 110.0).unwrap();
 
@@ -167,7 +317,7 @@ mod tests {
 
     #[test]
     fn test_is_over_bought_sold() {
-        let mut keeper = KdjKeeper::new(9, 3, 3);
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
         // Add enough data to get meaningful values
         for i in 0..20 {
             keeper.add(110.0 + i as f64, 100.0, 105.0 + i as f64).unwrap();
@@ -179,7 +329,7 @@ mod tests {
 
     #[test]
     fn test_is_peak_bottom() {
-        let mut keeper = KdjKeeper::new(9, 3, 3);
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
         for i in 0..20 {
             keeper.add(110.0 + i as f64, 100.0, 105.0 + i as f64).unwrap();
         }
@@ -187,5 +337,151 @@ mod tests {
         let result = keeper.is_peak_bottom(90.0, 10.0);
         assert!(result.is_finite());
     }
-}
 
+    #[test]
+    fn test_reset_timestamp_counter() {
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
+        for i in 0..5 {
+            keeper.add(110.0 + i as f64, 100.0, 105.0 + i as f64).unwrap();
+        }
+        assert_eq!(keeper.get_timestamp_counter(), 6);
+
+        keeper.reset();
+        assert_eq!(keeper.get_timestamp_counter(), 1);
+        assert_eq!(keeper.length(), 0);
+        assert_eq!(keeper.get(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_typical_input_mode() {
+        let mut close_keeper = KdjKeeper::new(9, 1, 1).unwrap();
+        let mut typical_keeper = KdjKeeper::with_input_mode(9, 1, 1, KdjInputMode::Typical).unwrap();
+        assert_eq!(close_keeper.get_input_mode(), KdjInputMode::Close);
+
+        close_keeper.add(110.0, 100.0, 105.0).unwrap();
+        typical_keeper.add(110.0, 100.0, 105.0).unwrap();
+        close_keeper.add(120.0, 100.0, 115.0).unwrap();
+        typical_keeper.add(120.0, 100.0, 115.0).unwrap();
+
+        // Window is 100..120, close gives 100 * 15 / 20
+        assert!((close_keeper.get().0 - 75.0).abs() < 1e-9);
+        // Typical price (120 + 100 + 115) / 3 replaces the close in the numerator
+        let typical = (120.0 + 100.0 + 115.0) / 3.0;
+        let expected = 100.0 * (typical - 100.0) / 20.0;
+        assert!((typical_keeper.get().0 - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_neutral_signals_are_zero() {
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
+        assert_eq!(keeper.is_over_bought_sold(80.0, 20.0), 0.0);
+        assert_eq!(keeper.is_cross_golden_death(30.0, 70.0), 0.0);
+        assert_eq!(keeper.is_peak_bottom(100.0, 0.0), 0.0);
+
+        keeper.add(110.0, 100.0, 105.0).unwrap();
+        keeper.add(112.0, 102.0, 107.0).unwrap();
+        // D sits between the thresholds and J inside the peak/bottom band
+        assert_eq!(keeper.is_over_bought_sold(99.0, 1.0), 0.0);
+        assert_eq!(keeper.is_peak_bottom(1000.0, -1000.0), 0.0);
+    }
+
+    #[test]
+    fn test_ema_smoothing() {
+        let bars = [(110.0, 100.0, 105.0), (115.0, 105.0, 112.0), (112.0, 104.0, 106.0)];
+        let mut sma_keeper = KdjKeeper::new(9, 3, 3).unwrap();
+        let mut ema_keeper = KdjKeeper::with_smoothing(9, 3, 3, Smoothing::Ema).unwrap();
+        assert_eq!(sma_keeper.get_smoothing(), Smoothing::Sma);
+        assert_eq!(ema_keeper.get_smoothing(), Smoothing::Ema);
+        for (high, low, close) in bars {
+            sma_keeper.add(high, low, close).unwrap();
+            ema_keeper.add(high, low, close).unwrap();
+        }
+
+        // Fast %K over the window 100..115 is 50, 80, 40
+        // SMA(3): K = 170 / 3, D = (50 + 65 + 170 / 3) / 3
+        let (k, d, j) = sma_keeper.get();
+        assert!((k - 170.0 / 3.0).abs() < 1e-9);
+        assert!((d - (115.0 + 170.0 / 3.0) / 3.0).abs() < 1e-9);
+        assert!((j - (3.0 * k - 2.0 * d)).abs() < 1e-9);
+
+        // EMA(3) with alpha 0.5 seeded at 50: K = 50, 65, 52.5 and D = 50, 57.5, 55
+        let (k, d, j) = ema_keeper.get();
+        assert!((k - 52.5).abs() < 1e-9);
+        assert!((d - 55.0).abs() < 1e-9);
+        assert!((j - 47.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let params = KdjParams {
+            period_fast_k: 9,
+            period_slow_k: 3,
+            period_slow_d: 3,
+            input_mode: KdjInputMode::Typical,
+            smoothing: Smoothing::Ema,
+        };
+        let keeper = KdjKeeper::from_params(params).unwrap();
+        assert_eq!(keeper.params(), params);
+        assert_eq!(keeper.get_input_mode(), KdjInputMode::Typical);
+    }
+
+    #[test]
+    fn test_prev_lags_by_one() {
+        let mut keeper = KdjKeeper::new(9, 3, 3).unwrap();
+        let bars = [
+            (110.0, 100.0, 105.0),
+            (115.0, 105.0, 112.0),
+            (112.0, 104.0, 106.0),
+            (108.0, 101.0, 102.0),
+        ];
+        let mut last = (0.0, 0.0, 0.0);
+        for (high, low, close) in bars {
+            keeper.add(high, low, close).unwrap();
+            assert_eq!(keeper.get_prev(), last);
+            last = keeper.get();
+        }
+    }
+
+    #[test]
+    fn test_error_on_zero_period() {
+        assert!(KdjKeeper::new(0, 3, 3).is_err());
+        assert!(KdjKeeper::new(9, 0, 3).is_err());
+        assert!(KdjKeeper::with_smoothing(9, 3, 0, Smoothing::Ema).is_err());
+    }
+
+    #[test]
+    fn test_get_clamped() {
+        let mut kdj = KdjKeeper::new(3, 1, 1).unwrap();
+        kdj.add(101.0, 99.0, 100.0).unwrap();
+        // Closes far outside the high/low range push K and D out of bounds
+        kdj.add(101.0, 99.0, 150.0).unwrap();
+        let (k, d, _) = kdj.get();
+        assert!(k > 100.0 && d > 100.0);
+        assert_eq!(kdj.get_clamped(), (100.0, 100.0, 100.0));
+
+        kdj.add(101.0, 99.0, 20.0).unwrap();
+        let (k, _, j) = kdj.get();
+        assert!(k < 0.0 && j < 0.0);
+        assert_eq!(kdj.get_clamped(), (0.0, 0.0, 0.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = KdjKeeper::new(3, 2, 2).unwrap();
+        let (keeper, restored) = assert_serde_round_trip(keeper, |keeper, _, price| {
+            keeper.add(price + 1.0, price - 1.5, price).unwrap()
+        });
+        assert_eq!(restored.get(), keeper.get());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || KdjKeeper::new(3, 2, 2).unwrap(),
+            |keeper, _, price| keeper.add(price + 1.0, price - 1.0, price).unwrap(),
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get(), keeper.get_prev()),
+        );
+    }
+}
@@ -12,3 +12,34 @@ pub mod tick_price_keeper;
 pub mod std_keeper;
 pub mod std_percentage_keeper;
 
+pub mod atr_ratio_keeper;
+pub mod donchian_channel_keeper;
+pub mod offline;
+pub mod cum_delta_keeper;
+pub mod streak_keeper;
+pub mod bias_keeper;
+pub mod adx_keeper;
+pub mod percentile_keeper;
+pub mod indicator;
+pub mod pipeline;
+pub mod vwap_keeper;
+pub mod ema_keeper;
+pub mod wma_keeper;
+pub mod smoothing;
+pub mod candle_builder;
+pub mod volatility_ratio_keeper;
+pub mod ema_cross_keeper;
+pub mod williams_r_keeper;
+pub mod fib_keeper;
+pub mod drawdown_keeper;
+pub mod tsi_keeper;
+pub mod coppock_keeper;
+pub mod float;
+pub mod mass_index_keeper;
+pub mod force_index_keeper;
+pub mod eom_keeper;
+pub mod indicator_cache;
+pub mod vol_index_keeper;
+
+#[cfg(test)]
+mod test_utils;
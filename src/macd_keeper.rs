@@ -1,11 +1,29 @@
 use std::collections::VecDeque;
 
-use crate::sma_keeper::SmaKeeper;
+use crate::common_utils::CrossSignal;
+use crate::smoothing::{MovingAverage, Smoothing, SmoothingKeeper};
 
+/// `check_cross` compares the latest MACD value with the one 5 samples back
+const CROSS_LOOKBACK: usize = 5;
+
+/// Construction parameters of a `MacdKeeper`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacdParams {
+    pub slow_period: usize,
+    pub fast_period: usize,
+    pub dea_period: usize,
+    pub divergen_wind: usize,
+    pub smoothing: Smoothing,
+    pub history_len: usize,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MacdKeeper {
-    slow_sma: SmaKeeper,
-    fast_sma: SmaKeeper,
-    dea_sma: SmaKeeper,
+    slow_ma: SmoothingKeeper,
+    fast_ma: SmoothingKeeper,
+    dea_ma: SmoothingKeeper,
+    smoothing: Smoothing,
     slow_sma_history: VecDeque<f64>,
     fast_sma_history: VecDeque<f64>,
     diff_line_history: VecDeque<f64>,
@@ -16,14 +34,14 @@ pub struct MacdKeeper {
     fast_period: usize,
     dea_period: usize,
     divergen_wind: usize,
-    top_trigger_price: f64,
-    top_trigger_macd: f64,
-    bot_trigger_price: f64,
-    bot_trigger_macd: f64,
+    history_len: usize,
     timestamp_counter: u64,
+    prev_dif_above_dea: Option<bool>,
 }
 
 impl MacdKeeper {
+    /// Creates a keeper with EMA slow, fast and DEA lines.
+    /// Use `with_smoothing(..., Smoothing::Sma)` for the previous SMA-based lines.
     pub fn new(
         slow_period: usize,
         fast_period: usize,
@@ -31,10 +49,72 @@ impl MacdKeeper {
         divergen_wind: usize,
         prices: Option<Vec<f64>>,
     ) -> Self {
+        Self::with_smoothing(
+            slow_period,
+            fast_period,
+            dea_period,
+            divergen_wind,
+            prices,
+            Smoothing::Ema,
+        )
+    }
+
+    /// Creates a keeper with EMA slow, fast and DEA lines, same as `new`
+    #[deprecated(note = "same as new")]
+    pub fn new_ema(
+        slow_period: usize,
+        fast_period: usize,
+        dea_period: usize,
+        divergen_wind: usize,
+        prices: Option<Vec<f64>>,
+    ) -> Self {
+        Self::with_smoothing(
+            slow_period,
+            fast_period,
+            dea_period,
+            divergen_wind,
+            prices,
+            Smoothing::Ema,
+        )
+    }
+
+    /// Creates a keeper whose slow, fast and DEA lines use the given moving average type
+    pub fn with_smoothing(
+        slow_period: usize,
+        fast_period: usize,
+        dea_period: usize,
+        divergen_wind: usize,
+        prices: Option<Vec<f64>>,
+        smoothing: Smoothing,
+    ) -> Self {
+        Self::with_history_len(
+            slow_period,
+            fast_period,
+            dea_period,
+            divergen_wind,
+            prices,
+            smoothing,
+            slow_period.max(divergen_wind),
+        )
+    }
+
+    /// Creates a keeper retaining `history_len` values of the slow, fast, DIF and DEA lines.
+    /// The length is raised to at least `divergen_wind` and the 5 samples `check_cross` needs.
+    pub fn with_history_len(
+        slow_period: usize,
+        fast_period: usize,
+        dea_period: usize,
+        divergen_wind: usize,
+        prices: Option<Vec<f64>>,
+        smoothing: Smoothing,
+        history_len: usize,
+    ) -> Self {
+        let history_len = history_len.max(divergen_wind).max(CROSS_LOOKBACK);
         let mut keeper = MacdKeeper {
-            slow_sma: SmaKeeper::new(slow_period, 0, 0.0),
-            fast_sma: SmaKeeper::new(fast_period, 0, 0.0),
-            dea_sma: SmaKeeper::new(dea_period, 0, 0.0),
+            slow_ma: smoothing.keeper(slow_period),
+            fast_ma: smoothing.keeper(fast_period),
+            dea_ma: smoothing.keeper(dea_period),
+            smoothing,
             slow_sma_history: VecDeque::new(),
             fast_sma_history: VecDeque::new(),
             diff_line_history: VecDeque::new(),
@@ -45,18 +125,16 @@ impl MacdKeeper {
             fast_period,
             dea_period,
             divergen_wind,
-            top_trigger_price: 3.0,
-            top_trigger_macd: -3.0,
-            bot_trigger_price: -3.0,
-            bot_trigger_macd: 3.0,
+            history_len,
             timestamp_counter: 1,
+            prev_dif_above_dea: None,
         };
 
         // Maintain max length for history arrays
-        keeper.slow_sma_history = VecDeque::new();
-        keeper.fast_sma_history = VecDeque::new();
-        keeper.diff_line_history = VecDeque::new();
-        keeper.dea_sma_history = VecDeque::new();
+        keeper.slow_sma_history = VecDeque::with_capacity(history_len + 1);
+        keeper.fast_sma_history = VecDeque::with_capacity(history_len + 1);
+        keeper.diff_line_history = VecDeque::with_capacity(history_len + 1);
+        keeper.dea_sma_history = VecDeque::with_capacity(history_len + 1);
         keeper.macd_line_history = VecDeque::with_capacity(divergen_wind);
         keeper.price_history = VecDeque::with_capacity(divergen_wind);
 
@@ -69,34 +147,59 @@ impl MacdKeeper {
         keeper
     }
 
+    pub fn from_params(params: MacdParams) -> Self {
+        Self::with_history_len(
+            params.slow_period,
+            params.fast_period,
+            params.dea_period,
+            params.divergen_wind,
+            None,
+            params.smoothing,
+            params.history_len,
+        )
+    }
+
+    pub fn params(&self) -> MacdParams {
+        MacdParams {
+            slow_period: self.slow_period,
+            fast_period: self.fast_period,
+            dea_period: self.dea_period,
+            divergen_wind: self.divergen_wind,
+            smoothing: self.smoothing,
+            history_len: self.history_len,
+        }
+    }
+
+    /// Adds a price. The inner averages are keyed by the internal counter rather than any
+    /// external clock, so replaying a session with earlier timestamps is never rejected.
     pub fn add(&mut self, price: f64) {
-        self.slow_sma.add(self.timestamp_counter, price);
-        self.fast_sma.add(self.timestamp_counter, price);
+        self.slow_ma.add(self.timestamp_counter, price);
+        self.fast_ma.add(self.timestamp_counter, price);
         self.timestamp_counter += 1;
 
-        let diff = self.fast_sma.get() - self.slow_sma.get();
-        self.dea_sma.add(self.timestamp_counter, diff);
+        let diff = self.fast_ma.get() - self.slow_ma.get();
+        self.dea_ma.add(self.timestamp_counter, diff);
         self.timestamp_counter += 1;
 
         // Update history arrays
-        self.slow_sma_history.push_back(self.slow_sma.get());
-        self.fast_sma_history.push_back(self.fast_sma.get());
+        self.slow_sma_history.push_back(self.slow_ma.get());
+        self.fast_sma_history.push_back(self.fast_ma.get());
         self.diff_line_history.push_back(diff);
-        self.dea_sma_history.push_back(self.dea_sma.get());
-        self.macd_line_history.push_back(diff - self.dea_sma.get());
+        self.dea_sma_history.push_back(self.dea_ma.get());
+        self.macd_line_history.push_back(diff - self.dea_ma.get());
         self.price_history.push_back(price);
 
         // Maintain max length for history arrays
-        while self.slow_sma_history.len() > 10 {
+        while self.slow_sma_history.len() > self.history_len {
             self.slow_sma_history.pop_front();
         }
-        while self.fast_sma_history.len() > 10 {
+        while self.fast_sma_history.len() > self.history_len {
             self.fast_sma_history.pop_front();
         }
-        while self.diff_line_history.len() > 10 {
+        while self.diff_line_history.len() > self.history_len {
             self.diff_line_history.pop_front();
         }
-        while self.dea_sma_history.len() > 10 {
+        while self.dea_sma_history.len() > self.history_len {
             self.dea_sma_history.pop_front();
         }
         while self.macd_line_history.len() > self.divergen_wind {
@@ -107,18 +210,50 @@ impl MacdKeeper {
         }
     }
 
+    pub fn get_history_len(&self) -> usize {
+        self.history_len
+    }
+
     pub fn size(&self) -> usize {
         self.slow_sma_history.len()
     }
 
+    pub fn get_smoothing(&self) -> Smoothing {
+        self.smoothing
+    }
+
+    pub fn get_timestamp_counter(&self) -> u64 {
+        self.timestamp_counter
+    }
+
+    /// Clears all lines and histories, keeping the configured periods
+    pub fn reset(&mut self) {
+        self.slow_ma.reset();
+        self.fast_ma.reset();
+        self.dea_ma.reset();
+        self.slow_sma_history.clear();
+        self.fast_sma_history.clear();
+        self.diff_line_history.clear();
+        self.dea_sma_history.clear();
+        self.macd_line_history.clear();
+        self.price_history.clear();
+        self.timestamp_counter = 1;
+        self.prev_dif_above_dea = None;
+    }
+
+    /// True once `divergen_wind` prices have been added; signals are neutral before that
+    pub fn is_ready(&self) -> bool {
+        self.price_history.len() >= self.divergen_wind
+    }
+
     pub fn check_cross(&self) -> bool {
-        if self.diff_line_history.len() < 5 {
+        if !self.is_ready() || self.diff_line_history.len() < CROSS_LOOKBACK {
             return false;
         }
 
         let macd_last = self.macd_line_history.back().copied().unwrap_or(0.0);
-        let macd_prev = if self.macd_line_history.len() >= 5 {
-            self.macd_line_history.get(self.macd_line_history.len() - 5)
+        let macd_prev = if self.macd_line_history.len() >= CROSS_LOOKBACK {
+            self.macd_line_history.get(self.macd_line_history.len() - CROSS_LOOKBACK)
                 .copied()
                 .unwrap_or(0.0)
         } else {
@@ -132,8 +267,25 @@ impl MacdKeeper {
         true
     }
 
+    /// Checks whether DIF crossed the DEA signal line since the last poll.
+    /// Returns the signal once per crossing; equal DIF and DEA keep the previous side.
+    pub fn poll_signal_cross(&mut self) -> Option<CrossSignal> {
+        let dif = self.diff_line_history.back().copied()?;
+        let dea = self.dea_sma_history.back().copied()?;
+        if dif == dea {
+            return None;
+        }
+
+        let above = dif > dea;
+        match self.prev_dif_above_dea.replace(above) {
+            Some(false) if above => Some(CrossSignal::GoldenCross),
+            Some(true) if !above => Some(CrossSignal::DeathCross),
+            _ => None,
+        }
+    }
+
     pub fn check_divergence(&self) -> f64 {
-        if self.macd_line_history.len() < self.divergen_wind {
+        if !self.is_ready() {
             return 0.0;
         }
 
@@ -161,6 +313,9 @@ impl MacdKeeper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_macd_new() {
@@ -187,9 +342,8 @@ mod tests {
         for i in 0..10 {
             keeper.add(100.0 + i as f64);
         }
-        let result = keeper.check_cross();
         // Result depends on the actual MACD values
-        assert!(result || !result); // Just check it doesn't panic
+        let _ = keeper.check_cross(); // Just check it doesn't panic
     }
 
     #[test]
@@ -220,5 +374,200 @@ mod tests {
         let keeper = MacdKeeper::new(26, 12, 9, 20, Some(prices));
         assert_eq!(keeper.size(), 4);
     }
-}
 
+    #[test]
+    fn test_reset_timestamp_counter() {
+        let mut keeper = MacdKeeper::new(26, 12, 9, 20, None);
+        assert_eq!(keeper.get_timestamp_counter(), 1);
+        for i in 0..10 {
+            keeper.add(100.0 + i as f64);
+        }
+        assert_eq!(keeper.get_timestamp_counter(), 21);
+
+        keeper.reset();
+        assert_eq!(keeper.get_timestamp_counter(), 1);
+        assert_eq!(keeper.size(), 0);
+        assert_eq!(keeper.check_divergence(), 0.0);
+    }
+
+    #[test]
+    fn test_poll_signal_cross() {
+        let mut keeper = MacdKeeper::with_smoothing(5, 2, 3, 10, None, Smoothing::Sma);
+        let mut signals = Vec::new();
+
+        // Falling prices keep DIF below DEA, the rebound pushes it above
+        for price in [110.0, 108.0, 106.0, 104.0, 102.0, 100.0, 103.0, 106.0, 109.0] {
+            keeper.add(price);
+            signals.push(keeper.poll_signal_cross());
+        }
+        assert_eq!(signals[6], Some(CrossSignal::GoldenCross));
+        assert_eq!(signals.iter().flatten().count(), 1);
+
+        // Rolling over pushes DIF back below DEA
+        signals.clear();
+        for price in [112.0, 115.0, 112.0, 109.0, 106.0] {
+            keeper.add(price);
+            signals.push(keeper.poll_signal_cross());
+        }
+        assert_eq!(signals[2], Some(CrossSignal::DeathCross));
+        assert_eq!(signals.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn test_with_smoothing() {
+        let prices = vec![100.0, 102.0, 101.0, 105.0];
+        let sma_keeper =
+            MacdKeeper::with_smoothing(3, 2, 2, 10, Some(prices.clone()), Smoothing::Sma);
+        let ema_keeper = MacdKeeper::new(3, 2, 2, 10, Some(prices));
+        assert_eq!(sma_keeper.get_smoothing(), Smoothing::Sma);
+        assert_eq!(ema_keeper.get_smoothing(), Smoothing::Ema);
+        assert_ne!(
+            sma_keeper.diff_line_history.back(),
+            ema_keeper.diff_line_history.back()
+        );
+    }
+
+    #[test]
+    fn test_signals_neutral_until_ready() {
+        let mut keeper = MacdKeeper::new(5, 2, 3, 8, None);
+        // Choppy warm-up prices must not produce early signals
+        for i in 0..7 {
+            keeper.add(if i % 2 == 0 { 100.0 } else { 90.0 - i as f64 });
+            assert!(!keeper.is_ready());
+            assert!(!keeper.check_cross());
+            assert_eq!(keeper.check_divergence(), 0.0);
+        }
+
+        keeper.add(80.0);
+        assert!(keeper.is_ready());
+        assert_eq!(keeper.size(), 8);
+    }
+
+    #[test]
+    fn test_replayed_sessions_fully_processed() {
+        let session = [100.0, 102.0, 101.0, 104.0, 106.0, 105.0];
+        let mut keeper = MacdKeeper::with_smoothing(4, 2, 2, 10, None, Smoothing::Sma);
+        for price in session {
+            keeper.add(price);
+        }
+        // Replaying the same session again must not be gated out
+        for price in session {
+            keeper.add(price + 10.0);
+        }
+
+        assert_eq!(keeper.get_timestamp_counter(), 1 + 2 * 2 * session.len() as u64);
+        assert_eq!(keeper.price_history.len(), 10);
+        assert_eq!(keeper.price_history.back().copied(), Some(115.0));
+        // Slow SMA covers the last four replayed prices
+        let expected_slow = (111.0 + 114.0 + 116.0 + 115.0) / 4.0;
+        assert_eq!(keeper.slow_sma_history.back().copied(), Some(expected_slow));
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        let params = MacdParams {
+            slow_period: 26,
+            fast_period: 12,
+            dea_period: 9,
+            divergen_wind: 20,
+            smoothing: Smoothing::Ema,
+            history_len: 40,
+        };
+        let keeper = MacdKeeper::from_params(params);
+        assert_eq!(keeper.params(), params);
+        assert_eq!(MacdKeeper::new(26, 12, 9, 20, None).params().smoothing, Smoothing::Ema);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_params_serde_round_trip() {
+        let params = MacdKeeper::with_smoothing(26, 12, 9, 20, None, Smoothing::Wma).params();
+        let json = serde_json::to_string(&params).unwrap();
+        let restored: MacdParams = serde_json::from_str(&json).unwrap();
+        assert_eq!(MacdKeeper::from_params(restored).params(), params);
+    }
+
+    #[test]
+    fn test_ema_matches_hand_computed() {
+        let mut keeper = MacdKeeper::new(26, 12, 9, 20, Some(vec![100.0, 102.0]));
+        assert_eq!(keeper.get_smoothing(), Smoothing::Ema);
+
+        // All lines are seeded at 100, then move by alpha = 2 / (period + 1) of the change
+        let slow = 100.0 + 2.0 / 27.0 * 2.0;
+        let fast = 100.0 + 2.0 / 13.0 * 2.0;
+        let dif = fast - slow;
+        let dea = 2.0 / 10.0 * dif;
+        assert!((keeper.slow_sma_history.back().unwrap() - slow).abs() < 1e-12);
+        assert!((keeper.fast_sma_history.back().unwrap() - fast).abs() < 1e-12);
+        assert!((keeper.diff_line_history.back().unwrap() - dif).abs() < 1e-12);
+        assert!((keeper.dea_sma_history.back().unwrap() - dea).abs() < 1e-12);
+        assert!((keeper.macd_line_history.back().unwrap() - (dif - dea)).abs() < 1e-12);
+
+        for price in [101.0, 105.0, 107.0, 104.0] {
+            keeper.add(price);
+        }
+        assert!((keeper.diff_line_history.back().unwrap() - 1.066677509801508).abs() < 1e-9);
+        assert!((keeper.dea_sma_history.back().unwrap() - 0.47515476660787587).abs() < 1e-9);
+        assert!((keeper.macd_line_history.back().unwrap() - 0.5915227431936321).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_new_uses_ema_lines() {
+        let mut keeper = MacdKeeper::new(26, 12, 9, 20, None);
+        for price in [100.0, 102.0, 101.0, 105.0, 107.0, 104.0] {
+            keeper.add(price);
+        }
+
+        // 12/26/9 EMA MACD computed by hand, all lines seeded by the first price
+        let dif = 1.066677509801508;
+        let dea = 0.47515476660787587;
+        assert!((keeper.diff_line_history.back().unwrap() - dif).abs() < 1e-6);
+        assert!((keeper.dea_sma_history.back().unwrap() - dea).abs() < 1e-6);
+        assert!((keeper.macd_line_history.back().unwrap() - (dif - dea)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_history_len() {
+        let mut keeper = MacdKeeper::new(26, 12, 9, 20, None);
+        assert_eq!(keeper.get_history_len(), 26);
+        for i in 0..40 {
+            keeper.add(100.0 + i as f64);
+        }
+        assert_eq!(keeper.size(), 26);
+        assert_eq!(keeper.diff_line_history.len(), 26);
+        assert_eq!(keeper.dea_sma_history.len(), 26);
+
+        // Too short a history is raised to cover the divergence window and cross lookback
+        let keeper = MacdKeeper::with_history_len(26, 12, 9, 8, None, Smoothing::Ema, 3);
+        assert_eq!(keeper.get_history_len(), 8);
+        let keeper = MacdKeeper::with_history_len(4, 2, 2, 3, None, Smoothing::Ema, 1);
+        assert_eq!(keeper.get_history_len(), 5);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = MacdKeeper::new(4, 2, 2, 5, None);
+        let (keeper, restored) =
+            assert_serde_round_trip(keeper, |keeper, _, price| keeper.add(price));
+        assert_eq!(restored.check_cross(), keeper.check_cross());
+        assert_eq!(restored.check_divergence(), keeper.check_divergence());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || MacdKeeper::new(4, 2, 2, 5, None),
+            |keeper, _, price| keeper.add(price),
+            |keeper| keeper.reset(),
+            |keeper| {
+                (
+                    keeper.size(),
+                    keeper.check_cross(),
+                    keeper.check_divergence(),
+                    keeper.get_timestamp_counter(),
+                )
+            },
+        );
+    }
+}
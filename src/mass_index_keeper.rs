@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+
+use crate::ema_keeper::EmaKeeper;
+
+/// Reading the index must exceed to arm a reversal bulge
+pub const BULGE_SETUP: f64 = 27.0;
+/// Reading the armed index must drop below to complete a reversal bulge
+pub const BULGE_TRIGGER: f64 = 26.5;
+
+/// Mass Index: sum over `sum_period` bars of `EMA(range) / EMA(EMA(range))`, where range is
+/// `high - low`. Range bulges push it above `BULGE_SETUP`.
+pub struct MassIndexKeeper {
+    sum_period: usize,
+    single_ema: EmaKeeper,
+    double_ema: EmaKeeper,
+    ratios: VecDeque<f64>,
+    mass_index: f64,
+    bulge_armed: bool,
+    reversal_bulge: bool,
+    timestamp_counter: u64,
+}
+
+impl MassIndexKeeper {
+    /// Classic settings are `new(9, 25)`
+    pub fn new(ema_period: usize, sum_period: usize) -> Result<Self, String> {
+        if ema_period < 1 || sum_period < 1 {
+            return Err("Mass index periods at least 1".to_string());
+        }
+
+        Ok(MassIndexKeeper {
+            sum_period,
+            single_ema: EmaKeeper::new(ema_period),
+            double_ema: EmaKeeper::new(ema_period),
+            ratios: VecDeque::with_capacity(sum_period + 1),
+            mass_index: 0.0,
+            bulge_armed: false,
+            reversal_bulge: false,
+            timestamp_counter: 1,
+        })
+    }
+
+    pub fn add(&mut self, high: f64, low: f64) {
+        let ts = self.timestamp_counter;
+        let single = self.single_ema.add(ts, high - low);
+        let double = self.double_ema.add(ts, single);
+        self.timestamp_counter += 1;
+
+        let ratio = if double == 0.0 { 1.0 } else { single / double };
+        self.ratios.push_back(ratio);
+        while self.ratios.len() > self.sum_period {
+            self.ratios.pop_front();
+        }
+        self.mass_index = self.ratios.iter().sum();
+
+        self.reversal_bulge = false;
+        if !self.is_ready() {
+            return;
+        }
+        if self.mass_index > BULGE_SETUP {
+            self.bulge_armed = true;
+        } else if self.bulge_armed && self.mass_index < BULGE_TRIGGER {
+            self.bulge_armed = false;
+            self.reversal_bulge = true;
+        }
+    }
+
+    /// Whether the full `sum_period` of ratios is available
+    pub fn is_ready(&self) -> bool {
+        self.ratios.len() >= self.sum_period
+    }
+
+    pub fn get(&self) -> f64 {
+        self.mass_index
+    }
+
+    /// Whether the latest bar completed a reversal bulge: the index rose above `BULGE_SETUP`
+    /// and has now dropped below `BULGE_TRIGGER`
+    pub fn is_reversal_bulge(&self) -> bool {
+        self.reversal_bulge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mass_index_new() {
+        assert!(MassIndexKeeper::new(9, 25).is_ok());
+        assert!(MassIndexKeeper::new(0, 25).is_err());
+    }
+
+    #[test]
+    fn test_constant_range() {
+        let mut keeper = MassIndexKeeper::new(9, 25).unwrap();
+        for _ in 0..30 {
+            keeper.add(101.0, 99.0);
+        }
+        assert!(keeper.is_ready());
+        assert!((keeper.get() - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_widening_range_bulge() {
+        let mut keeper = MassIndexKeeper::new(9, 25).unwrap();
+        let mut ranges = vec![2.0; 30];
+        ranges.extend((1..16).map(|i| 2.0 * 1.12f64.powi(i)));
+        ranges.extend(vec![2.0 * 1.12f64.powi(15); 45]);
+
+        let mut peak: f64 = 0.0;
+        let mut bulges = Vec::new();
+        for (i, range) in ranges.iter().enumerate() {
+            keeper.add(100.0 + range / 2.0, 100.0 - range / 2.0);
+            peak = peak.max(keeper.get());
+            if keeper.is_reversal_bulge() {
+                bulges.push(i);
+            }
+        }
+        assert!(peak > BULGE_SETUP);
+        // Fires once, when the index settles back below the trigger
+        assert_eq!(bulges, vec![73]);
+    }
+}
@@ -1,42 +1,108 @@
 use std::collections::VecDeque;
 use std::error::Error;
 
-pub struct MinMaxKeeper {
-    values_arr: VecDeque<f64>,
-    max_arr: VecDeque<f64>,
-    min_arr: VecDeque<f64>,
+use crate::float::Float;
+
+/// The window may hold up to `max_len * OVERFLOW_MULTIPLIER` values while the range stays
+/// within `target_range`, after which the oldest values are evicted regardless of range
+const OVERFLOW_MULTIPLIER: usize = 10;
+
+/// Rolling min/max over a window of values. Construct with `with_capacity` for the range-adaptive
+/// window, `fixed_window` for a plain last-`period` window or `with_time_window_ms` for the values
+/// of the last `window_ms`. Values are `T`, `f64` by default.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinMaxKeeper<T: Float = f64> {
+    values_arr: VecDeque<T>,
+    max_arr: VecDeque<T>,
+    min_arr: VecDeque<T>,
     max_len: usize,
-    target_range: f64,
+    target_range: T,
+    fixed_window: bool,
     last_ts: u64,
+    window_ms: Option<u64>,
+    /// Timestamp of each entry in `values_arr`, kept only with a time window
+    ts_arr: VecDeque<u64>,
 }
 
+/// Constructors without a value argument build the default `f64` keeper; their `_typed`
+/// variants pick the float type
 impl MinMaxKeeper {
-    fn new() -> Self {
-        eprintln!("warning init empty MinMaxKeeper");
-        MinMaxKeeper {
-            values_arr: VecDeque::new(),
-            max_arr: VecDeque::new(),
-            min_arr: VecDeque::new(),
-            max_len: 0,
-            target_range: 0.0001,
-            last_ts: 0,
-        }
+    /// Creates a keeper holding the values added within the last `window_ms` of the newest
+    /// timestamp, with no count limit. Feed it with `add_at` or `add_per_second`; `add` stamps
+    /// values with the latest timestamp seen.
+    pub fn with_time_window_ms(window_ms: u64) -> Self {
+        Self::with_time_window_ms_typed(window_ms)
+    }
+
+    /// Creates a keeper that always holds the last `period` values, with no range-based
+    /// eviction and no overflow beyond `period`
+    pub fn fixed_window(period: usize) -> Self {
+        Self::fixed_window_typed(period)
     }
+}
 
-    pub fn with_capacity(period: usize, target_range: f64) -> Self {
+impl<T: Float> MinMaxKeeper<T> {
+    /// Creates a keeper whose value window is pre-allocated for `period * OVERFLOW_MULTIPLIER`
+    /// values, the most it can hold, so adding never reallocates the window. The monotonic
+    /// min/max deques are pre-allocated for `period` values, all a full fixed window needs.
+    pub fn with_capacity(period: usize, target_range: T) -> Self {
         let mut keeper = MinMaxKeeper {
-            values_arr: VecDeque::new(),
-            max_arr: VecDeque::new(),
-            min_arr: VecDeque::new(),
+            values_arr: VecDeque::with_capacity(period * OVERFLOW_MULTIPLIER),
+            max_arr: VecDeque::with_capacity(period),
+            min_arr: VecDeque::with_capacity(period),
             max_len: period,
             target_range,
+            fixed_window: false,
             last_ts: 0,
+            window_ms: None,
+            ts_arr: VecDeque::new(),
         };
         keeper.set_max_len(period);
         keeper
     }
 
-    fn add_tail(&mut self, value: f64) {
+    /// Like `with_time_window_ms`, computing in `T`
+    pub fn with_time_window_ms_typed(window_ms: u64) -> Self {
+        MinMaxKeeper {
+            values_arr: VecDeque::new(),
+            max_arr: VecDeque::new(),
+            min_arr: VecDeque::new(),
+            max_len: usize::MAX,
+            target_range: T::ZERO,
+            fixed_window: false,
+            last_ts: 0,
+            window_ms: Some(window_ms),
+            ts_arr: VecDeque::new(),
+        }
+    }
+
+    /// Like `fixed_window`, computing in `T`, e.g. `MinMaxKeeper::<f32>::fixed_window_typed(14)`
+    pub fn fixed_window_typed(period: usize) -> Self {
+        let mut keeper = Self::with_capacity(period, T::ZERO);
+        keeper.fixed_window = true;
+        keeper
+    }
+
+    /// Whether the oldest value must be evicted before adding another
+    fn should_evict(&self) -> bool {
+        if self.fixed_window {
+            return self.values_arr.len() >= self.max_len;
+        }
+        if self.values_arr.len() >= self.max_len * OVERFLOW_MULTIPLIER {
+            return true;
+        }
+        if self.values_arr.len() < self.max_len {
+            return false;
+        }
+        // The range ratio is undefined without a positive min; keep a plain `max_len` window
+        let min = self.get_min();
+        if min <= T::ZERO {
+            return true;
+        }
+        (self.get_max() - min) / min > self.target_range
+    }
+
+    fn add_tail(&mut self, value: T) {
         while !self.min_arr.is_empty() && value < *self.min_arr.back().unwrap() {
             self.min_arr.pop_back();
         }
@@ -48,7 +114,7 @@ impl MinMaxKeeper {
         self.max_arr.push_back(value);
     }
 
-    fn remove_head(&mut self, value: f64) -> Result<(), Box<dyn Error>> {
+    fn remove_head(&mut self, value: T) -> Result<(), Box<dyn Error>> {
         if !self.min_arr.is_empty() {
             if value < *self.min_arr.front().unwrap() {
                 return Err(format!(
@@ -77,38 +143,94 @@ impl MinMaxKeeper {
         Ok(())
     }
 
-    pub fn add_per_second(&mut self, timestamp_ms: u64, value: f64) -> Result<(), Box<dyn Error>> {
+    fn evict_front(&mut self) -> Result<(), Box<dyn Error>> {
+        self.remove_head(*self.values_arr.front().unwrap())?;
+        self.values_arr.pop_front();
+        self.ts_arr.pop_front();
+        Ok(())
+    }
+
+    fn push(&mut self, timestamp_ms: u64, value: T) -> Result<(), Box<dyn Error>> {
         if self.max_len == 0 {
             return Err("MinMaxKeeper max_len is 0".into());
         }
+        if self.window_ms.is_some() {
+            self.expire(timestamp_ms)?;
+            self.ts_arr.push_back(timestamp_ms);
+        } else {
+            while self.should_evict() {
+                self.evict_front()?;
+            }
+        }
+        self.add_tail(value);
+        self.values_arr.push_back(value);
+        Ok(())
+    }
+
+    pub fn add_per_second(&mut self, timestamp_ms: u64, value: T) -> Result<(), Box<dyn Error>> {
         if timestamp_ms > self.last_ts + 1000 {
             self.last_ts = timestamp_ms;
-            while self.values_arr.len() >= self.max_len * 10
-                || (self.values_arr.len() >= self.max_len
-                    && (self.get_max() - self.get_min()) / self.get_min() > self.target_range)
-            {
-                self.remove_head(self.values_arr.front().unwrap().clone())?;
-                self.values_arr.pop_front();
+            self.push(timestamp_ms, value)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a value at `timestamp_ms`. Only a time window uses the timestamp; other windows
+    /// treat this like `add`.
+    pub fn add_at(&mut self, timestamp_ms: u64, value: T) -> Result<(), Box<dyn Error>> {
+        self.push(timestamp_ms, value)
+    }
+
+    pub fn add(&mut self, value: T) -> Result<(), Box<dyn Error>> {
+        let timestamp_ms = self.ts_arr.back().copied().unwrap_or(0);
+        self.push(timestamp_ms, value)
+    }
+
+    /// Evicts values older than the time window as of `timestamp_ms`, so the min and max age
+    /// out even while no values arrive. Does nothing without a time window.
+    pub fn expire(&mut self, timestamp_ms: u64) -> Result<(), Box<dyn Error>> {
+        let window_ms = match self.window_ms {
+            Some(window_ms) => window_ms,
+            None => return Ok(()),
+        };
+        while let Some(&oldest_ts) = self.ts_arr.front() {
+            if oldest_ts + window_ms > timestamp_ms {
+                break;
             }
-            self.add_tail(value);
-            self.values_arr.push_back(value);
+            self.evict_front()?;
         }
         Ok(())
     }
 
-    pub fn add(&mut self, value: f64) -> Result<(), Box<dyn Error>> {
-        if self.max_len == 0 {
-            return Err("MinMaxKeeper max_len is 0".into());
+    /// Appends the values of `other` after this keeper's values, oldest first.
+    /// Trimming follows this keeper's max_len and target_range; `other`'s settings are ignored.
+    /// A time-window keeper keeps `other`'s timestamps, so `other` must be a time-window keeper
+    /// whose values are no older than this keeper's newest.
+    pub fn merge(&mut self, other: &MinMaxKeeper<T>) -> Result<(), Box<dyn Error>> {
+        if self.window_ms.is_none() {
+            for value in other.values_arr.iter() {
+                self.add(*value)?;
+            }
+            return Ok(());
+        }
+
+        if other.window_ms.is_none() {
+            return Err("cannot merge a count window into a time window".into());
         }
-        while self.values_arr.len() >= self.max_len * 10
-            || (self.values_arr.len() >= self.max_len
-                && (self.get_max() - self.get_min()) / self.get_min() > self.target_range)
+        if let (Some(&newest_ts), Some(&oldest_other_ts)) =
+            (self.ts_arr.back(), other.ts_arr.front())
         {
-            self.remove_head(self.values_arr.front().unwrap().clone())?;
-            self.values_arr.pop_front();
+            if oldest_other_ts < newest_ts {
+                return Err(format!(
+                    "merged values start at {}ms, before the newest value at {}ms",
+                    oldest_other_ts, newest_ts
+                )
+                .into());
+            }
+        }
+        for (ts, value) in other.ts_arr.iter().zip(other.values_arr.iter()) {
+            self.add_at(*ts, *value)?;
         }
-        self.add_tail(value);
-        self.values_arr.push_back(value);
         Ok(())
     }
 
@@ -116,28 +238,43 @@ impl MinMaxKeeper {
         self.values_arr.len()
     }
 
-    pub fn get_max(&self) -> f64 {
-        self.max_arr.front().copied().unwrap_or(0.0)
+    pub fn get_max(&self) -> T {
+        self.max_arr.front().copied().unwrap_or(T::ZERO)
     }
 
-    pub fn get_min(&self) -> f64 {
-        self.min_arr.front().copied().unwrap_or(0.0)
+    pub fn get_min(&self) -> T {
+        self.min_arr.front().copied().unwrap_or(T::ZERO)
     }
 
-    pub fn get_mid(&self) -> f64 {
-        (self.get_max() + self.get_min()) / 2.0
+    /// Gets the window maximum, None while the window is empty
+    pub fn get_max_opt(&self) -> Option<T> {
+        self.max_arr.front().copied()
+    }
+
+    /// Gets the window minimum, None while the window is empty
+    pub fn get_min_opt(&self) -> Option<T> {
+        self.min_arr.front().copied()
+    }
+
+    pub fn get_mid(&self) -> T {
+        (self.get_max() + self.get_min()) / T::from_f64(2.0)
     }
 
     pub fn get_max_len(&self) -> usize {
         self.max_len
     }
 
-    pub fn get_now_max(&self) -> f64 {
-        self.max_arr.front().copied().unwrap_or(0.0)
+    /// Gets the time window, None for count-based windows
+    pub fn get_window_ms(&self) -> Option<u64> {
+        self.window_ms
+    }
+
+    pub fn get_now_max(&self) -> T {
+        self.max_arr.front().copied().unwrap_or(T::ZERO)
     }
 
-    pub fn get_now_min(&self) -> f64 {
-        self.min_arr.front().copied().unwrap_or(0.0)
+    pub fn get_now_min(&self) -> T {
+        self.min_arr.front().copied().unwrap_or(T::ZERO)
     }
 
     pub fn debug(&self) {
@@ -148,18 +285,30 @@ impl MinMaxKeeper {
         self.max_len = max_len;
     }
 
-    pub fn set_target_range(&mut self, target_range: f64) {
+    pub fn set_target_range(&mut self, target_range: T) {
         self.target_range = target_range;
     }
 
     pub fn is_full(&self) -> bool {
         self.values_arr.len() >= self.max_len
     }
+
+    /// Clears all stored values, keeping max_len and target_range
+    pub fn reset(&mut self) {
+        self.values_arr.clear();
+        self.max_arr.clear();
+        self.min_arr.clear();
+        self.ts_arr.clear();
+        self.last_ts = 0;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_basic_operations() {
@@ -273,8 +422,7 @@ mod tests {
 
     #[test]
     fn test_error_on_zero_max_len() {
-        let mut keeper = MinMaxKeeper::new();
-        // keeper has max_len = 0
+        let mut keeper = MinMaxKeeper::with_capacity(0, 0.0001);
         let result = keeper.add(1.0);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("max_len is 0"));
@@ -385,4 +533,208 @@ mod tests {
         // Should handle division by zero gracefully in the range check
         assert!(keeper.get_len() > 0);
     }
+
+    #[test]
+    fn test_no_reallocation_after_construction() {
+        let mut keeper = MinMaxKeeper::with_capacity(5, 0.0001);
+        let capacity = keeper.values_arr.capacity();
+        assert!(capacity >= 5 * OVERFLOW_MULTIPLIER);
+
+        for i in 0..200 {
+            keeper.add(100.0 + (i % 7) as f64 * 0.001).unwrap();
+        }
+        assert_eq!(keeper.values_arr.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = MinMaxKeeper::with_capacity(10, 1.0);
+        for value in [5.0, 3.0, 8.0] {
+            first.add(value).unwrap();
+        }
+        let mut second = MinMaxKeeper::with_capacity(2, 0.0);
+        for value in [9.0, 4.0, 6.0] {
+            second.add(value).unwrap();
+        }
+        // second only holds its last two values under its own max_len
+        assert_eq!(second.get_len(), 2);
+
+        first.merge(&second).unwrap();
+        assert_eq!(first.get_len(), 5);
+        assert_eq!(first.get_max(), 8.0);
+        assert_eq!(first.get_min(), 3.0);
+
+        // Merging into a short window trims with the target's max_len
+        let mut short = MinMaxKeeper::with_capacity(2, 0.0);
+        short.add(1.0).unwrap();
+        short.merge(&first).unwrap();
+        assert_eq!(short.get_len(), 2);
+        assert_eq!(short.get_max(), 6.0);
+        assert_eq!(short.get_min(), 4.0);
+    }
+
+    #[test]
+    fn test_fixed_window() {
+        let mut keeper = MinMaxKeeper::fixed_window(5);
+        for i in 0..4 {
+            keeper.add(100.0 + i as f64).unwrap();
+        }
+        assert_eq!(keeper.get_len(), 4);
+
+        // Never evicts on range, never grows past the period
+        for i in 4..20 {
+            keeper.add(100.0 + i as f64).unwrap();
+            assert_eq!(keeper.get_len(), 5);
+        }
+        assert_eq!(keeper.get_min(), 115.0);
+        assert_eq!(keeper.get_max(), 119.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = MinMaxKeeper::with_capacity(4, 0.0001);
+        let (keeper, restored) =
+            assert_serde_round_trip(keeper, |keeper, _, price| keeper.add(price).unwrap());
+        assert_eq!(restored.get_max(), keeper.get_max());
+        assert_eq!(restored.get_min(), keeper.get_min());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || MinMaxKeeper::with_capacity(3, 0.0001),
+            |keeper, _, price| keeper.add(price).unwrap(),
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get_max(), keeper.get_min(), keeper.get_len()),
+        );
+    }
+
+    #[test]
+    fn test_zero_min_falls_back_to_count_eviction() {
+        let mut keeper = MinMaxKeeper::with_capacity(3, 0.0001);
+        let mut per_second = MinMaxKeeper::with_capacity(3, 0.0001);
+        // A 0/0 range ratio is NaN and used to let zeros pile up to the overflow cap
+        let values: [f64; 9] = [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0];
+        for (i, value) in values.iter().enumerate() {
+            keeper.add(*value).unwrap();
+            per_second.add_per_second((i as u64 + 1) * 2000, *value).unwrap();
+            assert!(keeper.get_len() <= 3);
+            assert!(per_second.get_len() <= 3);
+            assert!(!keeper.get_mid().is_nan());
+        }
+        assert_eq!(keeper.get_min(), 2.0);
+        assert_eq!(per_second.get_min(), 2.0);
+    }
+
+    #[test]
+    fn test_f32_matches_f64() {
+        let mut keeper_f64 = MinMaxKeeper::<f64>::fixed_window_typed(3);
+        let mut keeper_f32 = MinMaxKeeper::<f32>::fixed_window_typed(3);
+        for value in [5.0, 3.0, 8.0, 6.0, 4.0] {
+            keeper_f64.add(value).unwrap();
+            keeper_f32.add(value as f32).unwrap();
+        }
+        assert_eq!((keeper_f64.get_min(), keeper_f64.get_max()), (4.0, 8.0));
+        assert_eq!((keeper_f32.get_min(), keeper_f32.get_max()), (4.0, 8.0));
+        assert_eq!(keeper_f32.get_mid(), 6.0);
+    }
+
+    #[test]
+    fn test_get_opt_distinguishes_empty() {
+        let mut keeper = MinMaxKeeper::fixed_window(3);
+        assert_eq!(keeper.get_max_opt(), None);
+        assert_eq!(keeper.get_min_opt(), None);
+        assert_eq!(keeper.get_max(), 0.0);
+
+        keeper.add(0.0).unwrap();
+        assert_eq!(keeper.get_max_opt(), Some(0.0));
+        assert_eq!(keeper.get_min_opt(), Some(0.0));
+        keeper.add(-2.5).unwrap();
+        assert_eq!(keeper.get_min_opt(), Some(-2.5));
+
+        keeper.reset();
+        assert_eq!(keeper.get_max_opt(), None);
+    }
+
+    #[test]
+    fn test_time_window_ages_out_extremes() {
+        let mut keeper = MinMaxKeeper::with_time_window_ms(5000);
+        assert_eq!(keeper.get_window_ms(), Some(5000));
+        keeper.add_at(1000, 110.0).unwrap();
+        keeper.add_at(2000, 90.0).unwrap();
+        keeper.add_at(3000, 100.0).unwrap();
+        keeper.add_at(3500, 101.0).unwrap();
+        assert_eq!((keeper.get_min(), keeper.get_max()), (90.0, 110.0));
+
+        // 110.0 at 1000ms leaves the window at 6000ms
+        keeper.add_at(6000, 99.0).unwrap();
+        assert_eq!((keeper.get_min(), keeper.get_max()), (90.0, 101.0));
+        assert_eq!(keeper.get_len(), 4);
+
+        // 90.0 at 2000ms leaves at 7000ms even with no new value
+        keeper.expire(7000).unwrap();
+        assert_eq!((keeper.get_min(), keeper.get_max()), (99.0, 101.0));
+
+        // Untimestamped values take the latest timestamp, 6000ms
+        keeper.add(95.0).unwrap();
+        keeper.expire(10_999).unwrap();
+        assert_eq!((keeper.get_min(), keeper.get_max()), (95.0, 99.0));
+        keeper.expire(11_000).unwrap();
+        assert_eq!(keeper.get_max_opt(), None);
+    }
+
+    #[test]
+    fn test_time_window_merge_keeps_timestamps() {
+        let mut first = MinMaxKeeper::with_time_window_ms(3500);
+        first.add_at(1000, 5.0).unwrap();
+        let mut second = MinMaxKeeper::with_time_window_ms(3500);
+        second.add_at(2000, 9.0).unwrap();
+        second.add_at(5000, 1.0).unwrap();
+
+        // 5.0 at 1000ms ages out when 1.0 arrives at 5000ms, 9.0 at 2000ms stays
+        first.merge(&second).unwrap();
+        assert_eq!((first.get_min(), first.get_max()), (1.0, 9.0));
+        assert_eq!(first.get_len(), 2);
+
+        // 9.0 leaves on its own timestamp rather than the merge target's
+        first.expire(5500).unwrap();
+        assert_eq!((first.get_min(), first.get_max()), (1.0, 1.0));
+
+        // Older values or untimestamped windows cannot be appended
+        let mut older = MinMaxKeeper::with_time_window_ms(3500);
+        older.add_at(4000, 2.0).unwrap();
+        assert!(first.merge(&older).is_err());
+        assert!(first.merge(&MinMaxKeeper::fixed_window(3)).is_err());
+        assert_eq!(first.get_len(), 1);
+    }
+
+    #[test]
+    fn test_time_window_add_per_second() {
+        let mut keeper = MinMaxKeeper::with_time_window_ms(3000);
+        for (ts, value) in [(1001, 5.0), (1500, 50.0), (2002, 4.0), (3003, 6.0), (4004, 7.0)] {
+            keeper.add_per_second(ts, value).unwrap();
+        }
+        // 50.0 is throttled and 5.0 at 1001ms aged out at 4004ms
+        assert_eq!((keeper.get_min(), keeper.get_max()), (4.0, 7.0));
+        assert_eq!(keeper.get_len(), 3);
+    }
+
+    #[test]
+    fn test_monotonic_deques_preallocated() {
+        let mut keeper = MinMaxKeeper::fixed_window(5);
+        let max_capacity = keeper.max_arr.capacity();
+        let min_capacity = keeper.min_arr.capacity();
+        assert!(max_capacity >= 5);
+        assert!(min_capacity >= 5);
+
+        // Rising runs fill min_arr and falling runs fill max_arr to the full window
+        for i in 0..200 {
+            let step = (i % 10) as f64;
+            let value = if (i / 10) % 2 == 0 { step } else { -step };
+            keeper.add(value).unwrap();
+        }
+        assert_eq!(keeper.max_arr.capacity(), max_capacity);
+        assert_eq!(keeper.min_arr.capacity(), min_capacity);
+    }
 }
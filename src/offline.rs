@@ -0,0 +1,61 @@
+use crate::bollinger_band_keeper::BollingerBandKeeper;
+
+/// Computes %B for every price by driving a BollingerBandKeeper over the slice.
+///
+/// # Arguments
+/// * `prices` - Slice of prices
+/// * `period` - Bollinger window size
+/// * `mult` - Standard deviation multiplier
+///
+/// # Returns
+/// Vector of %B values (same length as input, None while the window is warming up
+/// or when the bands have zero width)
+pub fn percent_b_series(prices: &[f64], period: usize, mult: f64) -> Vec<Option<f64>> {
+    let mut keeper = BollingerBandKeeper::with_window(period, mult, None);
+
+    prices
+        .iter()
+        .map(|&price| {
+            keeper.add(price);
+            if keeper.size() < period {
+                None
+            } else {
+                keeper.percent_b(price)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_b_series_warm_up() {
+        let prices = [100.0, 101.0, 103.0, 102.0, 104.0];
+        let series = percent_b_series(&prices, 3, 2.0);
+        assert_eq!(series.len(), prices.len());
+        assert_eq!(series[0], None);
+        assert_eq!(series[1], None);
+        assert!(series[2].is_some());
+    }
+
+    #[test]
+    fn test_percent_b_series_matches_live_keeper() {
+        let prices = [100.0, 101.0, 103.0, 102.0, 104.0, 99.0, 98.5, 101.5];
+        let series = percent_b_series(&prices, 5, 2.0);
+
+        let mut keeper = BollingerBandKeeper::with_window(5, 2.0, None);
+        for &price in &prices {
+            keeper.add(price);
+        }
+        let last = *prices.last().unwrap();
+        assert_eq!(*series.last().unwrap(), keeper.percent_b(last));
+    }
+
+    #[test]
+    fn test_percent_b_series_flat_prices() {
+        let series = percent_b_series(&[100.0; 4], 2, 2.0);
+        assert!(series.iter().all(|v| v.is_none()));
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+/// How a percentile between two ranks is resolved, following numpy's `percentile` methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PercentileMethod {
+    /// Interpolates linearly between the closest ranks
+    #[default]
+    Linear,
+    /// Takes the closest rank, rounding halves to even
+    Nearest,
+}
+
+/// Rolling window of values supporting percentile and rank queries
+pub struct PercentileKeeper {
+    values: VecDeque<f64>,
+    period: usize,
+    method: PercentileMethod,
+    min_window: usize,
+}
+
+impl PercentileKeeper {
+    pub fn new(period: usize) -> Self {
+        Self::with_method(period, PercentileMethod::Linear)
+    }
+
+    pub fn with_method(period: usize, method: PercentileMethod) -> Self {
+        PercentileKeeper {
+            values: VecDeque::with_capacity(period + 1),
+            period,
+            method,
+            min_window: 1,
+        }
+    }
+
+    /// Sets how many values the window needs before `percentile` answers, at least 1
+    pub fn set_min_window(&mut self, min_window: usize) {
+        self.min_window = min_window.max(1);
+    }
+
+    pub fn get_method(&self) -> PercentileMethod {
+        self.method
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.values.push_back(value);
+        while self.values.len() > self.period {
+            self.values.pop_front();
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Gets the `percentile` (0..=100) of the window resolved by the keeper's method,
+    /// 0.0 while the window holds fewer than the minimum window of values
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        if self.values.len() < self.min_window {
+            return 0.0;
+        }
+
+        let mut sorted: Vec<f64> = self.values.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+        if self.method == PercentileMethod::Nearest {
+            return sorted[rank.round_ties_even() as usize];
+        }
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+    }
+
+    /// Percent of the other window values that are below the most recent value:
+    /// 100.0 when it is the window maximum, 0.0 when it is the minimum or with fewer than 2 values
+    pub fn current_rank(&self) -> f64 {
+        let latest = match self.values.back() {
+            Some(&latest) if self.values.len() >= 2 => latest,
+            _ => return 0.0,
+        };
+
+        let below = self.values.iter().filter(|&&v| v < latest).count();
+        100.0 * below as f64 / (self.values.len() - 1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let mut keeper = PercentileKeeper::new(5);
+        for value in [3.0, 1.0, 4.0, 2.0, 5.0] {
+            keeper.add(value);
+        }
+        assert_eq!(keeper.percentile(0.0), 1.0);
+        assert_eq!(keeper.percentile(50.0), 3.0);
+        assert_eq!(keeper.percentile(100.0), 5.0);
+        assert_eq!(keeper.percentile(25.0), 2.0);
+        assert_eq!(keeper.percentile(90.0), 4.6);
+    }
+
+    #[test]
+    fn test_window_rolls() {
+        let mut keeper = PercentileKeeper::new(3);
+        for value in [10.0, 1.0, 2.0, 3.0] {
+            keeper.add(value);
+        }
+        assert_eq!(keeper.size(), 3);
+        assert_eq!(keeper.percentile(100.0), 3.0);
+    }
+
+    #[test]
+    fn test_current_rank() {
+        let mut keeper = PercentileKeeper::new(5);
+        assert_eq!(keeper.current_rank(), 0.0);
+
+        for value in [1.0, 2.0, 4.0, 5.0, 3.0] {
+            keeper.add(value);
+        }
+        // Latest value is the median
+        assert!((keeper.current_rank() - 50.0).abs() < 1e-9);
+
+        keeper.add(10.0);
+        // Latest value is the maximum
+        assert!((keeper.current_rank() - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_methods_match_numpy() {
+        let mut linear = PercentileKeeper::new(6);
+        let mut nearest = PercentileKeeper::with_method(6, PercentileMethod::Nearest);
+        for value in [3.0, 1.0, 4.0, 2.0, 5.0, 7.0] {
+            linear.add(value);
+            nearest.add(value);
+        }
+        // np.percentile([3, 1, 4, 2, 5, 7], [10, 30, 50, 90], method=...)
+        let percentiles = [10.0, 30.0, 50.0, 90.0];
+        let linear_expected = [1.5, 2.5, 3.5, 6.0];
+        let nearest_expected = [1.0, 3.0, 3.0, 5.0];
+        for (i, percentile) in percentiles.iter().enumerate() {
+            assert!((linear.percentile(*percentile) - linear_expected[i]).abs() < 1e-12);
+            assert_eq!(nearest.percentile(*percentile), nearest_expected[i]);
+        }
+    }
+
+    #[test]
+    fn test_min_window() {
+        let mut keeper = PercentileKeeper::new(5);
+        keeper.set_min_window(3);
+        keeper.add(1.0);
+        keeper.add(2.0);
+        assert_eq!(keeper.percentile(50.0), 0.0);
+        keeper.add(3.0);
+        assert_eq!(keeper.percentile(50.0), 2.0);
+    }
+}
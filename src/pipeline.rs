@@ -0,0 +1,93 @@
+use crate::indicator::Indicator;
+
+/// Chain of indicators where each stage's output is fed into the next stage
+pub struct Pipeline {
+    stages: Vec<Box<dyn Indicator>>,
+    output: f64,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Pipeline {
+            stages: Vec::new(),
+            output: 0.0,
+        }
+    }
+
+    /// Appends a stage fed by the output of the previous stage
+    pub fn then<I: Indicator + 'static>(mut self, stage: I) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Drives `value` through every stage and returns the final output
+    pub fn push(&mut self, value: f64) -> f64 {
+        let mut current = value;
+        for stage in self.stages.iter_mut() {
+            current = stage.update(current);
+        }
+        self.output = current;
+        self.output
+    }
+
+    /// Gets the output of the last stage from the latest push
+    pub fn output(&self) -> f64 {
+        self.output
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rsi_keeper::RsiKeeper;
+    use crate::sma_keeper::SmaKeeper;
+
+    #[test]
+    fn test_empty_pipeline_passes_through() {
+        let mut pipeline = Pipeline::new();
+        assert!(pipeline.is_empty());
+        assert_eq!(pipeline.push(42.0), 42.0);
+    }
+
+    #[test]
+    fn test_sma_of_sma() {
+        let mut pipeline = Pipeline::new()
+            .then(SmaKeeper::new(3, 0, 0.0))
+            .then(SmaKeeper::new(2, 0, 0.0));
+        assert_eq!(pipeline.len(), 2);
+
+        let mut first = SmaKeeper::new(3, 0, 0.0);
+        let mut second = SmaKeeper::new(2, 0, 0.0);
+        for (i, price) in [100.0, 102.0, 101.0, 105.0, 107.0, 104.0].iter().enumerate() {
+            let ts = i as u64 + 1;
+            let expected = second.add(ts, first.add(ts, *price));
+            assert_eq!(pipeline.push(*price), expected);
+        }
+        assert_eq!(pipeline.output(), second.get());
+    }
+
+    #[test]
+    fn test_rsi_of_sma() {
+        let mut pipeline = Pipeline::new()
+            .then(SmaKeeper::new(2, 0, 0.0))
+            .then(RsiKeeper::with_period(14));
+        for price in [100.0, 101.0, 102.0, 103.0] {
+            pipeline.push(price);
+        }
+        assert!(pipeline.output() > 50.0);
+    }
+}
@@ -1,37 +1,73 @@
 use std::collections::VecDeque;
 
-fn is_near_zero(value: f64, epsilon: f64) -> bool {
-    value < epsilon && value > -epsilon
-}
+use crate::common_utils::round_to;
+use crate::float::Float;
+use crate::indicator::Indicator;
+use crate::streak_keeper::StreakKeeper;
 
-pub struct RsiKeeper {
+/// Relative Strength Index over the last `max_len` prices, computed in `T` (`f64` by default)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RsiKeeper<T: Float = f64> {
     max_len: usize,
-    rsi: f64,
-    prev_rsi: f64,
-    price_arr: VecDeque<f64>,
+    rsi: T,
+    prev_rsi: T,
+    price_arr: VecDeque<T>,
+    streak_keeper: StreakKeeper,
+}
+
+impl<T: Float> Default for RsiKeeper<T> {
+    fn default() -> Self {
+        Self::new_typed()
+    }
 }
 
+/// Constructors without a value argument build the default `f64` keeper; their `_typed`
+/// variants pick the float type
 impl RsiKeeper {
     pub fn new() -> Self {
+        Self::new_typed()
+    }
+
+    pub fn with_period(max_len: usize) -> Self {
+        Self::with_period_typed(max_len)
+    }
+}
+
+impl<T: Float> RsiKeeper<T> {
+    /// Like `new`, computing in `T`
+    pub fn new_typed() -> Self {
         eprintln!("warning: init empty rsi keeper. use new RsiKeeper(len) to create new RsiKeeper");
         RsiKeeper {
             max_len: 0,
-            rsi: 50.0,
-            prev_rsi: 50.0,
+            rsi: T::from_f64(50.0),
+            prev_rsi: T::from_f64(50.0),
             price_arr: VecDeque::with_capacity(10),
+            streak_keeper: StreakKeeper::new(),
         }
     }
 
-    pub fn with_period(max_len: usize) -> Self {
+    /// Like `with_period`, computing in `T`, e.g. `RsiKeeper::<f32>::with_period_typed(14)`
+    pub fn with_period_typed(max_len: usize) -> Self {
         RsiKeeper {
             max_len,
-            rsi: 50.0,
-            prev_rsi: 50.0,
+            rsi: T::from_f64(50.0),
+            prev_rsi: T::from_f64(50.0),
             price_arr: VecDeque::with_capacity(max_len),
+            streak_keeper: StreakKeeper::new(),
         }
     }
 
-    pub fn add(&mut self, price: f64) {
+    /// Clears the price window and streak, keeping the period
+    pub fn reset(&mut self) {
+        self.price_arr.clear();
+        self.rsi = T::from_f64(50.0);
+        self.prev_rsi = T::from_f64(50.0);
+        self.streak_keeper = StreakKeeper::new();
+    }
+
+    pub fn add(&mut self, price: T) {
+        self.streak_keeper.add(price.to_f64());
+
         self.price_arr.push_back(price);
         while self.price_arr.len() > self.max_len && self.max_len > 0 {
             self.price_arr.pop_front();
@@ -41,45 +77,88 @@ impl RsiKeeper {
             return;
         }
 
-        let mut gain = 0.0;
-        let mut loss = 0.0;
+        let mut gain = T::ZERO;
+        let mut loss = T::ZERO;
 
         // Calculate initial gain and loss
         for i in 1..self.price_arr.len() {
             let change = self.price_arr[i] - self.price_arr[i - 1];
-            if change > 0.0 {
+            if change > T::ZERO {
                 gain += change;
             } else {
                 loss -= change;
             }
         }
 
-        // Calculate the average gain and loss
-        gain /= self.max_len as f64;
-        loss /= self.max_len as f64;
+        // Average over the changes actually summed, fewer than max_len during warm-up
+        let changes = T::from_usize(self.price_arr.len() - 1);
+        gain /= changes;
+        loss /= changes;
 
         self.prev_rsi = self.rsi;
 
-        let rs = if is_near_zero(loss, 0.0001) {
-            100.0
+        // A flat window has no momentum either way, RSI of 50; gains with no losses mean an
+        // unbounded RS, RSI of 100. Both sums are exact zeros when no change was counted, so
+        // the check holds at any price scale.
+        let hundred = T::from_f64(100.0);
+        self.rsi = if loss == T::ZERO && gain == T::ZERO {
+            T::from_f64(50.0)
+        } else if loss == T::ZERO {
+            hundred
         } else {
-            gain / loss
+            hundred - (hundred / (T::ONE + gain / loss))
         };
-        self.rsi = 100.0 - (100.0 / (1.0 + rs));
     }
 
-    pub fn get_prev(&self) -> f64 {
+    /// Adds `values` in order and returns the final RSI
+    pub fn add_slice(&mut self, values: &[T]) -> T {
+        for value in values {
+            self.add(*value);
+        }
+        self.rsi
+    }
+
+    /// Adds a bar using its typical price `(high + low + close) / 3` instead of the close
+    pub fn add_hlc(&mut self, high: T, low: T, close: T) {
+        self.add((high + low + close) / T::from_f64(3.0));
+    }
+
+    /// Consecutive up (positive) or down (negative) price changes, 0 after an unchanged price
+    pub fn streak(&self) -> i64 {
+        i64::from(self.streak_keeper.get())
+    }
+
+    pub fn get_prev(&self) -> T {
         self.prev_rsi
     }
 
-    pub fn get(&self) -> f64 {
+    pub fn get(&self) -> T {
         self.rsi
     }
+
+    /// Gets the current RSI rounded to the nearest multiple of `tick_size`
+    pub fn get_rounded(&self, tick_size: f64) -> f64 {
+        round_to(self.get().to_f64(), tick_size)
+    }
+}
+
+impl Indicator for RsiKeeper {
+    fn update(&mut self, value: f64) -> f64 {
+        self.add(value);
+        self.get()
+    }
+
+    fn value(&self) -> f64 {
+        self.get()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
 
     #[test]
     fn test_rsi_new() {
@@ -143,7 +222,162 @@ mod tests {
         }
         let rsi = keeper.get();
         // RSI should be between 0 and 100
-        assert!(rsi >= 0.0 && rsi <= 100.0);
+        assert!((0.0..=100.0).contains(&rsi));
+    }
+
+    #[test]
+    fn test_add_hlc_matches_add_on_flat_bars() {
+        let mut hlc_keeper = RsiKeeper::with_period(14);
+        let mut close_keeper = RsiKeeper::with_period(14);
+        for close in [100.0, 101.0, 99.5, 102.0, 101.0] {
+            hlc_keeper.add_hlc(close, close, close);
+            close_keeper.add(close);
+        }
+        assert_eq!(hlc_keeper.get(), close_keeper.get());
+        assert_eq!(hlc_keeper.get_prev(), close_keeper.get_prev());
+    }
+
+    #[test]
+    fn test_streak() {
+        let mut keeper = RsiKeeper::with_period(14);
+        keeper.add(100.0);
+        assert_eq!(keeper.streak(), 0);
+        keeper.add(101.0);
+        keeper.add(102.0);
+        keeper.add(103.0);
+        assert_eq!(keeper.streak(), 3);
+
+        keeper.add(102.0);
+        assert_eq!(keeper.streak(), -1);
+        keeper.add(101.0);
+        assert_eq!(keeper.streak(), -2);
+        keeper.add(101.0);
+        assert_eq!(keeper.streak(), 0);
+    }
+
+    #[test]
+    fn test_warm_up_averages_actual_changes() {
+        let mut keeper = RsiKeeper::with_period(14);
+        for price in [100.0, 101.0, 102.0] {
+            keeper.add(price);
+        }
+        assert_eq!(keeper.get(), 100.0);
+
+        // Gains of 2 and losses of 1 over three changes give RS = 2
+        let mut keeper = RsiKeeper::with_period(14);
+        for price in [100.0, 102.0, 101.0, 101.0] {
+            keeper.add(price);
+        }
+        assert!((keeper.get() - 100.0 * 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    /// Cutler's RSI over all changes in `prices`, averaging by the number of changes
+    fn reference_rsi(prices: &[f64]) -> f64 {
+        let changes: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+        let n = changes.len() as f64;
+        let gain = changes.iter().filter(|c| **c > 0.0).sum::<f64>() / n;
+        let loss = -changes.iter().filter(|c| **c < 0.0).sum::<f64>() / n;
+        if loss == 0.0 {
+            return if gain == 0.0 { 50.0 } else { 100.0 };
+        }
+        100.0 - 100.0 / (1.0 + gain / loss)
+    }
+
+    #[test]
+    fn test_warm_up_matches_reference() {
+        let prices = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03,
+            45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64,
+        ];
+        let period = 14;
+        let mut keeper = RsiKeeper::with_period(period);
+        for (i, price) in prices.iter().enumerate() {
+            keeper.add(*price);
+            if i == 0 {
+                continue;
+            }
+            // During warm-up every price so far is in the window, afterwards the last `period`
+            let start = (i + 1).saturating_sub(period);
+            let expected = reference_rsi(&prices[start..=i]);
+            assert!(
+                (keeper.get() - expected).abs() < 1e-9,
+                "index {}: {} != {}",
+                i,
+                keeper.get(),
+                expected
+            );
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = RsiKeeper::with_period(4);
+        let (keeper, restored) =
+            assert_serde_round_trip(keeper, |keeper, _, price| keeper.add(price));
+        assert_eq!(restored.get(), keeper.get());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || RsiKeeper::with_period(4),
+            |keeper, _, price| keeper.add(price),
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get(), keeper.get_prev()),
+        );
     }
-}
 
+    #[test]
+    fn test_f32_matches_f64() {
+        let mut keeper_f64 = RsiKeeper::<f64>::with_period_typed(4);
+        let mut keeper_f32 = RsiKeeper::<f32>::with_period_typed(4);
+        for price in [100.0, 102.0, 101.0, 101.0, 103.5] {
+            keeper_f64.add(price);
+            keeper_f32.add(price as f32);
+        }
+        // The window of 102, 101, 101, 103.5 has gains of 2.5 and losses of 1, so RS = 2.5
+        assert!((keeper_f64.get() - (100.0 - 100.0 / 3.5)).abs() < 1e-12);
+        assert!((keeper_f32.get() as f64 - keeper_f64.get()).abs() < 1e-4);
+        assert_eq!(keeper_f32.streak(), keeper_f64.streak());
+    }
+
+    #[test]
+    fn test_add_slice_matches_add() {
+        let prices = [100.0, 101.0, 99.5, 102.0, 102.5, 101.0];
+        let mut batch = RsiKeeper::with_period(4);
+        let mut single = RsiKeeper::with_period(4);
+        let last = batch.add_slice(&prices);
+        for price in prices {
+            single.add(price);
+        }
+        assert_eq!(last, single.get());
+        assert_eq!(batch.get_prev(), single.get_prev());
+        assert_eq!(batch.streak(), single.streak());
+    }
+
+    #[test]
+    fn test_flat_window_is_neutral() {
+        let mut keeper = RsiKeeper::with_period(5);
+        for _ in 0..6 {
+            keeper.add(100.0);
+        }
+        assert_eq!(keeper.get(), 50.0);
+
+        keeper.add(100.5);
+        assert_eq!(keeper.get(), 100.0);
+    }
+
+    #[test]
+    fn test_low_priced_series_counts_small_losses() {
+        // Changes of a few 1e-5 are real moves for a sub-cent instrument
+        let prices = [0.00120, 0.00122, 0.00119, 0.00121, 0.00118];
+        let mut keeper = RsiKeeper::with_period(5);
+        for price in prices {
+            keeper.add(price);
+        }
+        let expected = reference_rsi(&prices);
+        assert!(expected < 50.0);
+        assert!((keeper.get() - expected).abs() < 1e-9);
+    }
+}
@@ -1,26 +1,44 @@
 use std::collections::VecDeque;
 
-pub struct SmaKeeper {
-    arr: VecDeque<f64>,
+use crate::common_utils::round_to;
+use crate::float::Float;
+use crate::indicator::Indicator;
+
+/// Simple moving average over the last `max_len` values, computed in `T` (`f64` by default)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmaKeeper<T: Float = f64> {
+    arr: VecDeque<T>,
     max_len: usize,
-    sma: f64,
-    prev_sma: f64,
-    sum: f64,
+    sma: T,
+    prev_sma: T,
+    sum: T,
+    mean: T,
+    m2: T,
+    evictions: usize,
+    dropped_count: u64,
     pub prev_timestamp: u64,
     time_gap_ms: u64,
+    initial_sma: T,
 }
 
-impl SmaKeeper {
-    /// Creates a new SmaKeeper with the specified maximum length, time gap, and initial SMA value
-    pub fn new(max_len: usize, time_gap_ms: u64, initial_sma: f64) -> Self {
+impl<T: Float> SmaKeeper<T> {
+    /// Creates a new SmaKeeper with the specified maximum length, time gap, and initial SMA value.
+    /// The window is pre-allocated for `max_len + 1` values (one pushed before the oldest is
+    /// popped), so adding never reallocates.
+    pub fn new(max_len: usize, time_gap_ms: u64, initial_sma: T) -> Self {
         SmaKeeper {
-            arr: VecDeque::new(),
+            arr: VecDeque::with_capacity(max_len + 1),
             max_len,
             sma: initial_sma,
-            prev_sma: 0.0,
-            sum: 0.0,
+            prev_sma: T::ZERO,
+            sum: T::ZERO,
+            mean: T::ZERO,
+            m2: T::ZERO,
+            evictions: 0,
+            dropped_count: 0,
             prev_timestamp: 0,
             time_gap_ms,
+            initial_sma,
         }
     }
 
@@ -35,8 +53,9 @@ impl SmaKeeper {
     }
 
     /// Adds a new value with timestamp, updating the SMA
-    pub fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+    pub fn add(&mut self, timestamp: u64, value: T) -> T {
         if timestamp < self.prev_timestamp + self.time_gap_ms {
+            self.dropped_count += 1;
             return self.sma;
         }
         self.prev_timestamp = timestamp;
@@ -44,25 +63,301 @@ impl SmaKeeper {
         self.arr.push_back(value);
         self.sum += value;
 
+        let mut evicted = None;
         while self.arr.len() > self.max_len {
             if let Some(remove) = self.arr.pop_front() {
                 self.sum -= remove;
+                self.evictions += 1;
+                evicted = Some(remove);
             }
         }
+        self.update_moments(value, evicted);
+
+        // Once the window has fully turned over, re-sum it to drop accumulated rounding error.
+        // This costs O(max_len) every max_len evictions, O(1) amortized.
+        if self.evictions >= self.max_len.max(1) {
+            self.sum = self.arr.iter().copied().sum();
+            self.resync_moments();
+            self.evictions = 0;
+        }
 
         self.prev_sma = self.sma; // Store previous SMA before updating
-        self.sma = self.sum / self.arr.len() as f64;
-        return self.sma;
+        self.sma = self.sum / T::from_usize(self.arr.len());
+        self.sma
+    }
+
+    /// Sliding-window Welford update of the running mean and sum of squared deviations,
+    /// O(1) per value, so the std keeps its precision at high price levels. `add` resyncs
+    /// the moments in two passes once every `max_len` evictions.
+    fn update_moments(&mut self, value: T, evicted: Option<T>) {
+        if self.arr.is_empty() {
+            self.mean = T::ZERO;
+            self.m2 = T::ZERO;
+            return;
+        }
+
+        let n = T::from_usize(self.arr.len());
+        let prev_mean = self.mean;
+        match evicted {
+            Some(old) => {
+                self.mean += (value - old) / n;
+                self.m2 += (value - old) * (value - self.mean + old - prev_mean);
+            }
+            None => {
+                self.mean += (value - prev_mean) / n;
+                self.m2 += (value - prev_mean) * (value - self.mean);
+            }
+        }
+        if self.m2 < T::ZERO {
+            self.m2 = T::ZERO;
+        }
+    }
+
+    /// Recomputes the mean and sum of squared deviations of the window in two passes
+    fn resync_moments(&mut self) {
+        if self.arr.is_empty() {
+            self.mean = T::ZERO;
+            self.m2 = T::ZERO;
+            return;
+        }
+        let mean = self.arr.iter().copied().sum::<T>() / T::from_usize(self.arr.len());
+        self.mean = mean;
+        self.m2 = self.arr.iter().map(|&v| (v - mean) * (v - mean)).sum();
+    }
+
+    /// Adds `values` in order at timestamps `start_ts`, `start_ts + step_ms`, ..., so the time
+    /// gap applies as if they were added one by one. Returns the final SMA.
+    pub fn add_slice(&mut self, start_ts: u64, step_ms: u64, values: &[T]) -> T {
+        for (i, value) in values.iter().enumerate() {
+            self.add(start_ts + i as u64 * step_ms, *value);
+        }
+        self.sma
+    }
+
+    /// Number of values rejected because they arrived within `time_gap_ms` of the last one
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped_count
+    }
+
+    /// Adds the typical price `(high + low + close) / 3` of a bar
+    pub fn add_ohlc(&mut self, timestamp: u64, high: T, low: T, close: T) -> T {
+        self.add(timestamp, (high + low + close) / T::from_f64(3.0))
     }
 
     /// Gets the current SMA value
-    pub fn get(&self) -> f64 {
+    pub fn get(&self) -> T {
         self.sma
     }
 
+    /// Gets the current SMA rounded to the nearest multiple of `tick_size`
+    pub fn get_rounded(&self, tick_size: f64) -> f64 {
+        round_to(self.get().to_f64(), tick_size)
+    }
+
+    /// Gets the population standard deviation of the window in O(1) from the running
+    /// Welford moments. 0.0 while the window is empty.
+    pub fn std(&self) -> T {
+        let n = self.arr.len();
+        if n == 0 {
+            return T::ZERO;
+        }
+        (self.m2 / T::from_usize(n)).sqrt()
+    }
+
+    /// Gets the sample standard deviation of the window (divides by `n - 1`),
+    /// 0.0 with fewer than 2 values
+    pub fn sample_std(&self) -> T {
+        let n = self.arr.len();
+        if n < 2 {
+            return T::ZERO;
+        }
+        (self.m2 / T::from_usize(n - 1)).sqrt()
+    }
+
     /// Gets the previous SMA value
-    pub fn get_prev(&self) -> f64 {
+    pub fn get_prev(&self) -> T {
         self.prev_sma
     }
+
+    /// Clears the window and restores the initial SMA, keeping the configured period and time gap
+    pub fn reset(&mut self) {
+        self.arr.clear();
+        self.sma = self.initial_sma;
+        self.prev_sma = T::ZERO;
+        self.sum = T::ZERO;
+        self.mean = T::ZERO;
+        self.m2 = T::ZERO;
+        self.evictions = 0;
+        self.dropped_count = 0;
+        self.prev_timestamp = 0;
+    }
 }
 
+impl Indicator for SmaKeeper {
+    /// Adds `value` at the earliest timestamp the time gap allows
+    fn update(&mut self, value: f64) -> f64 {
+        let timestamp = self.prev_timestamp + self.time_gap_ms;
+        self.add(timestamp, value)
+    }
+
+    fn value(&self) -> f64 {
+        self.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
+
+    #[test]
+    fn test_no_reallocation_after_construction() {
+        let mut keeper = SmaKeeper::new(20, 0, 0.0);
+        let capacity = keeper.arr.capacity();
+        assert!(capacity > 20);
+
+        for i in 0..60 {
+            keeper.add(i, i as f64);
+        }
+        assert!(keeper.is_full());
+        assert_eq!(keeper.arr.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_add_ohlc_matches_typical_price() {
+        let mut ohlc_keeper = SmaKeeper::new(3, 0, 0.0);
+        let mut keeper = SmaKeeper::new(3, 0, 0.0);
+        let bars: [(f64, f64, f64); 3] =
+            [(12.0, 9.0, 11.0), (13.0, 10.0, 10.0), (15.0, 11.0, 14.0)];
+        for (ts, (high, low, close)) in (1..).zip(bars) {
+            let typical = (high + low + close) / 3.0;
+            assert_eq!(ohlc_keeper.add_ohlc(ts, high, low, close), keeper.add(ts, typical));
+        }
+        assert!((ohlc_keeper.get() - 35.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_running_sum_does_not_drift() {
+        let mut keeper = SmaKeeper::new(10, 0, 0.0);
+        let mut ts = 0;
+        // Alternating huge and tiny magnitudes leave rounding residue in a naive running sum
+        for i in 0..1_000_000u64 {
+            ts += 1;
+            let value = if i % 2 == 0 { 1e9 + i as f64 * 0.37 } else { 1e-3 * i as f64 };
+            keeper.add(ts, value);
+        }
+        for _ in 0..10 {
+            ts += 1;
+            keeper.add(ts, 1.0);
+        }
+        assert!((keeper.get() - 1.0).abs() < 1e-12, "sma drifted to {}", keeper.get());
+    }
+
+    #[test]
+    fn test_dropped_count() {
+        let mut keeper = SmaKeeper::new(5, 1000, 0.0);
+        keeper.add(1000, 10.0);
+        keeper.add(1200, 11.0);
+        keeper.add(1500, 12.0);
+        keeper.add(1999, 13.0);
+        assert_eq!(keeper.dropped_count(), 3);
+        assert_eq!(keeper.size(), 1);
+
+        keeper.add(2000, 14.0);
+        assert_eq!(keeper.dropped_count(), 3);
+        assert_eq!(keeper.get(), 12.0);
+
+        keeper.reset();
+        assert_eq!(keeper.dropped_count(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = SmaKeeper::new(4, 0, 0.0);
+        let (keeper, restored) = assert_serde_round_trip(keeper, |keeper, i, price| {
+            keeper.add(i as u64 + 1, price);
+        });
+        assert_eq!(restored.get(), keeper.get());
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || SmaKeeper::new(3, 0, 0.0),
+            |keeper, i, price| {
+                keeper.add(i as u64 + 1, price);
+            },
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get(), keeper.get_prev(), keeper.size()),
+        );
+    }
+
+    #[test]
+    fn test_f32_matches_f64() {
+        let mut keeper_f64 = SmaKeeper::<f64>::new(3, 0, 0.0);
+        let mut keeper_f32 = SmaKeeper::<f32>::new(3, 0, 0.0);
+        for (ts, value) in (1..).zip([10.0, 20.0, 11.0, 14.5]) {
+            keeper_f64.add(ts, value);
+            keeper_f32.add(ts, value as f32);
+        }
+        assert_eq!(keeper_f64.get(), 45.5 / 3.0);
+        assert!((keeper_f32.get() - 45.5 / 3.0).abs() < 1e-5);
+        assert_eq!(keeper_f32.get_rounded(0.01), 15.17);
+    }
+
+    #[test]
+    fn test_add_slice_matches_add() {
+        let values = [10.0, 12.0, 11.0, 15.0, 14.0, 13.0];
+        let mut batch = SmaKeeper::new(4, 1000, 0.0);
+        let mut single = SmaKeeper::new(4, 1000, 0.0);
+        // A 500ms step lands every other value inside the 1000ms time gap
+        let last = batch.add_slice(1000, 500, &values);
+        for (i, value) in values.iter().enumerate() {
+            single.add(1000 + i as u64 * 500, *value);
+        }
+        assert_eq!(last, single.get());
+        assert_eq!(batch.get_prev(), single.get_prev());
+        assert_eq!(batch.size(), single.size());
+        assert_eq!(batch.dropped_count(), 3);
+        assert_eq!(batch.prev_timestamp, single.prev_timestamp);
+    }
+
+    #[test]
+    fn test_std() {
+        let mut keeper = SmaKeeper::new(4, 0, 0.0);
+        assert_eq!(keeper.std(), 0.0);
+        keeper.add(1, 7.0);
+        assert_eq!(keeper.std(), 0.0);
+        assert_eq!(keeper.sample_std(), 0.0);
+
+        // Window 2, 4, 4, 5 after 7 rolls out: mean 3.75, squared deviations sum 4.75
+        for (ts, value) in (2..).zip([2.0, 4.0, 4.0, 5.0]) {
+            keeper.add(ts, value);
+        }
+        assert!((keeper.std() - (4.75f64 / 4.0).sqrt()).abs() < 1e-12);
+        assert!((keeper.sample_std() - (4.75f64 / 3.0).sqrt()).abs() < 1e-12);
+
+        keeper.reset();
+        assert_eq!(keeper.std(), 0.0);
+    }
+
+    #[test]
+    fn test_std_precise_at_high_price_level() {
+        let mut keeper = SmaKeeper::new(200, 0, 0.0);
+        // 1100 values end mid-turnover, so the std comes from the incremental update
+        let values: Vec<f64> =
+            (0..1100).map(|i| 60_000.0 + (i as f64 * 0.37).sin() * 0.004).collect();
+        for (ts, value) in (1..).zip(&values) {
+            keeper.add(ts, *value);
+        }
+
+        let window = &values[values.len() - 200..];
+        let mean = window.iter().sum::<f64>() / 200.0;
+        let std = (window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 200.0).sqrt();
+        assert!(std > 0.002 && std < 0.004);
+        assert!((keeper.std() - std).abs() / std < 1e-8, "std {} vs {}", keeper.std(), std);
+    }
+}
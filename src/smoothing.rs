@@ -0,0 +1,170 @@
+use crate::ema_keeper::EmaKeeper;
+use crate::sma_keeper::SmaKeeper;
+use crate::wma_keeper::WmaKeeper;
+
+/// Common interface of the moving averages used as smoothing stages
+pub trait MovingAverage {
+    fn add(&mut self, timestamp: u64, value: f64) -> f64;
+    fn get(&self) -> f64;
+    fn get_prev(&self) -> f64;
+    fn size(&self) -> usize;
+    fn reset(&mut self);
+}
+
+/// Averaging used by the smoothing stages of composite indicators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Smoothing {
+    #[default]
+    Sma,
+    Ema,
+    Wma,
+}
+
+impl Smoothing {
+    /// Creates a moving average of this type over `period` values
+    pub fn keeper(&self, period: usize) -> SmoothingKeeper {
+        match self {
+            Smoothing::Sma => SmoothingKeeper::Sma(SmaKeeper::new(period, 0, 0.0)),
+            Smoothing::Ema => SmoothingKeeper::Ema(EmaKeeper::new(period)),
+            Smoothing::Wma => SmoothingKeeper::Wma(WmaKeeper::new(period)),
+        }
+    }
+}
+
+/// A moving average selected by `Smoothing`, dispatching to the concrete keeper
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SmoothingKeeper {
+    Sma(SmaKeeper),
+    Ema(EmaKeeper),
+    Wma(WmaKeeper),
+}
+
+impl SmoothingKeeper {
+    fn inner(&self) -> &dyn MovingAverage {
+        match self {
+            SmoothingKeeper::Sma(keeper) => keeper,
+            SmoothingKeeper::Ema(keeper) => keeper,
+            SmoothingKeeper::Wma(keeper) => keeper,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn MovingAverage {
+        match self {
+            SmoothingKeeper::Sma(keeper) => keeper,
+            SmoothingKeeper::Ema(keeper) => keeper,
+            SmoothingKeeper::Wma(keeper) => keeper,
+        }
+    }
+}
+
+impl MovingAverage for SmoothingKeeper {
+    fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        self.inner_mut().add(timestamp, value)
+    }
+
+    fn get(&self) -> f64 {
+        self.inner().get()
+    }
+
+    fn get_prev(&self) -> f64 {
+        self.inner().get_prev()
+    }
+
+    fn size(&self) -> usize {
+        self.inner().size()
+    }
+
+    fn reset(&mut self) {
+        self.inner_mut().reset()
+    }
+}
+
+impl MovingAverage for SmaKeeper {
+    fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        SmaKeeper::add(self, timestamp, value)
+    }
+
+    fn get(&self) -> f64 {
+        SmaKeeper::get(self)
+    }
+
+    fn get_prev(&self) -> f64 {
+        SmaKeeper::get_prev(self)
+    }
+
+    fn size(&self) -> usize {
+        SmaKeeper::size(self)
+    }
+
+    fn reset(&mut self) {
+        SmaKeeper::reset(self)
+    }
+}
+
+impl MovingAverage for EmaKeeper {
+    fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        EmaKeeper::add(self, timestamp, value)
+    }
+
+    fn get(&self) -> f64 {
+        EmaKeeper::get(self)
+    }
+
+    fn get_prev(&self) -> f64 {
+        EmaKeeper::get_prev(self)
+    }
+
+    fn size(&self) -> usize {
+        EmaKeeper::size(self)
+    }
+
+    fn reset(&mut self) {
+        EmaKeeper::reset(self)
+    }
+}
+
+impl MovingAverage for WmaKeeper {
+    fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        WmaKeeper::add(self, timestamp, value)
+    }
+
+    fn get(&self) -> f64 {
+        WmaKeeper::get(self)
+    }
+
+    fn get_prev(&self) -> f64 {
+        WmaKeeper::get_prev(self)
+    }
+
+    fn size(&self) -> usize {
+        WmaKeeper::size(self)
+    }
+
+    fn reset(&mut self) {
+        WmaKeeper::reset(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeper_dispatch() {
+        let values = [10.0, 20.0, 11.0];
+        let mut sma = Smoothing::Sma.keeper(3);
+        let mut ema = Smoothing::Ema.keeper(3);
+        let mut wma = Smoothing::Wma.keeper(3);
+        for (i, value) in values.iter().enumerate() {
+            sma.add(i as u64 + 1, *value);
+            ema.add(i as u64 + 1, *value);
+            wma.add(i as u64 + 1, *value);
+        }
+        assert!((sma.get() - 41.0 / 3.0).abs() < 1e-9);
+        assert_eq!(ema.get(), 13.0);
+        assert!((wma.get() - (10.0 + 40.0 + 33.0) / 6.0).abs() < 1e-9);
+        assert_eq!(sma.size(), 3);
+        assert_eq!(Smoothing::default(), Smoothing::Sma);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::common_utils::BUY;
 use crate::sma_keeper::SmaKeeper;
 use crate::tick_price_keeper::TickPriceKeeper;
 
@@ -10,6 +11,7 @@ pub struct StdKeeper {
     cached_std: f64,
     last_cache_timestamp: u64,
     period: usize,
+    adjust_side: Option<bool>,
 }
 
 impl StdKeeper {
@@ -20,6 +22,26 @@ impl StdKeeper {
     /// * `frequency_ms` - Frequency in milliseconds for caching SMA and STD
     /// * `max_length` - Maximum length for price history
     pub fn new(period: usize, frequency_ms: u64, max_length: usize) -> Self {
+        Self::build(period, frequency_ms, max_length, None)
+    }
+
+    /// Creates a StdKeeper whose SMA and STD track the price paid to trade on `side`:
+    /// mid plus half the spread (the ask) for `BUY`, mid minus half the spread (the bid) for `SELL`
+    pub fn with_spread_adjustment(
+        period: usize,
+        frequency_ms: u64,
+        max_length: usize,
+        side: bool,
+    ) -> Self {
+        Self::build(period, frequency_ms, max_length, Some(side))
+    }
+
+    fn build(
+        period: usize,
+        frequency_ms: u64,
+        max_length: usize,
+        adjust_side: Option<bool>,
+    ) -> Self {
         StdKeeper {
             sma_keeper: SmaKeeper::new(period, 0, 0.0),
             tick_price_keeper: TickPriceKeeper::new(frequency_ms as usize, max_length),
@@ -28,9 +50,33 @@ impl StdKeeper {
             cached_std: 0.0,
             last_cache_timestamp: 0,
             period,
+            adjust_side,
         }
     }
 
+    /// Creates a StdKeeper warmed up from historical mid prices.
+    /// The i-th mid is fed as a tick at `(i + 1) * frequency_ms`, so every mid is recorded.
+    pub fn from_prices(
+        period: usize,
+        frequency_ms: u64,
+        max_length: usize,
+        mids: &[f64],
+    ) -> Self {
+        let mut keeper = Self::new(period, frequency_ms, max_length);
+        for (i, mid) in mids.iter().enumerate() {
+            keeper.on_receive_tick((i as u64 + 1) * frequency_ms, *mid, *mid);
+        }
+        keeper
+    }
+
+    pub fn get_period(&self) -> usize {
+        self.period
+    }
+
+    pub fn get_last_timestamp(&self) -> u64 {
+        self.last_cache_timestamp
+    }
+
     /// Updates the current bid and ask prices
     pub fn on_receive_tick(&mut self, timestamp: u64, bid: f64, ask: f64) {
         self.tick_price_keeper.on_receive_tick(bid, ask);
@@ -39,10 +85,10 @@ impl StdKeeper {
                     // Update tick price keeper periodically
             self.tick_price_keeper.on_period_callback(timestamp);
             
-            // Update SMA with mid price
+            // Update SMA with mid price, or the spread-adjusted price
             let mid = (bid + ask) / 2.0;
             if mid > 0.0 {
-                self.sma_keeper.add(timestamp, mid);
+                self.sma_keeper.add(timestamp, self.price_of(bid, ask));
             }
             self.update_cache(timestamp);
         }
@@ -81,6 +127,17 @@ impl StdKeeper {
         }
     }
 
+    /// Price tracked for a quote: the mid, or the side's touch price when spread-adjusted
+    fn price_of(&self, bid: f64, ask: f64) -> f64 {
+        let mid = (bid + ask) / 2.0;
+        let half_spread = (ask - bid) / 2.0;
+        match self.adjust_side {
+            Some(side) if side == BUY => mid + half_spread,
+            Some(_) => mid - half_spread,
+            None => mid,
+        }
+    }
+
     /// Updates the cache with current SMA and STD values
     fn update_cache(&mut self, timestamp: u64) {
         self.cached_sma = self.sma_keeper.get();
@@ -88,55 +145,117 @@ impl StdKeeper {
         self.last_cache_timestamp = timestamp;
     }
 
-    /// Calculates the standard deviation from the tick price keeper history
+    /// Calculates the standard deviation of the SMA window from its running moments
     fn calculate_std(&self) -> f64 {
-        let size = self.tick_price_keeper.get_history_prices_size();
-        
-        if size == 0 {
-            return 0.0;
-        }
+        self.sma_keeper.std()
+    }
 
-        let mean = self.sma_keeper.get();
-        
-        // Use all available history or just the period
-        let end_index = size as i64;
-        let start_index = if size > self.period {
-            (size - self.period) as i64
-        } else {
-            0
-        };
+    /// Gets the tick price keeper (for advanced usage)
+    pub fn get_tick_price_keeper(&self) -> &TickPriceKeeper {
+        &self.tick_price_keeper
+    }
 
-        if end_index <= start_index {
-            return 0.0;
-        }
+    /// Gets the SMA keeper (for advanced usage)
+    pub fn get_sma_keeper(&self) -> &SmaKeeper {
+        &self.sma_keeper
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common_utils::SELL;
 
-        let mut total_diff = 0.0;
-        let count = (end_index - start_index) as usize;
-        
-        for i in start_index..end_index {
-            // Calculate mid price from bid and ask history
-            let bid = self.tick_price_keeper.get_history_bid(i);
-            let ask = self.tick_price_keeper.get_history_ask(i);
-            let price = (bid + ask) / 2.0;
-            let diff = price - mean;
-            total_diff += diff * diff;
+    #[test]
+    fn test_from_prices_matches_live_warm_up() {
+        let mids = [100.0, 101.0, 99.5, 102.0, 100.5, 103.0];
+        let seeded = StdKeeper::from_prices(4, 1000, 10, &mids);
+
+        let mut live = StdKeeper::new(4, 1000, 10);
+        for (i, mid) in mids.iter().enumerate() {
+            live.on_receive_tick((i as u64 + 1) * 1000, mid - 0.5, mid + 0.5);
         }
 
-        if count == 0 {
-            return 0.0;
+        let timestamp = seeded.get_last_timestamp();
+        assert_eq!(timestamp, live.get_last_timestamp());
+        let (seeded_sma, seeded_std) = seeded.get_sma_and_std(timestamp);
+        let (live_sma, live_std) = live.get_sma_and_std(timestamp);
+        assert!((seeded_sma - live_sma).abs() < 1e-12);
+        assert!((seeded_std - live_std).abs() < 1e-12);
+        assert!((seeded_sma - 101.25).abs() < 1e-12);
+        assert!(seeded_std > 0.0);
+    }
+
+    #[test]
+    fn test_spread_adjusted_bands() {
+        let quotes = [(99.0, 101.0), (100.0, 101.0), (99.5, 102.5), (101.0, 102.0)];
+        let mut mid_keeper = StdKeeper::new(4, 1000, 10);
+        let mut buy_keeper = StdKeeper::with_spread_adjustment(4, 1000, 10, BUY);
+        let mut sell_keeper = StdKeeper::with_spread_adjustment(4, 1000, 10, SELL);
+        for (i, (bid, ask)) in quotes.iter().enumerate() {
+            let ts = (i as u64 + 1) * 1000;
+            mid_keeper.on_receive_tick(ts, *bid, *ask);
+            buy_keeper.on_receive_tick(ts, *bid, *ask);
+            sell_keeper.on_receive_tick(ts, *bid, *ask);
         }
 
-        let variance = total_diff / count as f64;
-        variance.sqrt()
+        let ts = mid_keeper.get_last_timestamp();
+        // Mean mid 100.75, mean ask 101.625, mean bid 99.875
+        assert!((mid_keeper.get_sma(ts) - 100.75).abs() < 1e-12);
+        assert!((buy_keeper.get_sma(ts) - 101.625).abs() < 1e-12);
+        assert!((sell_keeper.get_sma(ts) - 99.875).abs() < 1e-12);
+
+        let asks: [f64; 4] = [101.0, 101.0, 102.5, 102.0];
+        let variance = asks.iter().map(|a| (a - 101.625).powi(2)).sum::<f64>() / 4.0;
+        assert!((buy_keeper.get_std(ts) - variance.sqrt()).abs() < 1e-12);
+        assert!(buy_keeper.get_std(ts) != mid_keeper.get_std(ts));
     }
 
-    /// Gets the tick price keeper (for advanced usage)
-    pub fn get_tick_price_keeper(&self) -> &TickPriceKeeper {
-        &self.tick_price_keeper
+    #[test]
+    fn test_std_matches_tick_walk() {
+        let mut keeper = StdKeeper::new(20, 100, 200);
+        for i in 0..500u64 {
+            let mid = 100.0 + (i as f64 * 0.7).sin() * 3.0 + i as f64 * 0.01;
+            let half_spread = 0.05 + (i % 3) as f64 * 0.01;
+            keeper.on_receive_tick((i + 1) * 100, mid - half_spread, mid + half_spread);
+        }
+
+        // Walk the last `period` recorded ticks around the SMA, as calculate_std used to
+        let ticks = keeper.get_tick_price_keeper();
+        let size = ticks.get_history_prices_size() as i64;
+        let mean = keeper.get_sma_keeper().get();
+        let variance = (size - 20..size)
+            .map(|i| ((ticks.get_history_bid(i) + ticks.get_history_ask(i)) / 2.0 - mean).powi(2))
+            .sum::<f64>()
+            / 20.0;
+
+        let ts = keeper.get_last_timestamp();
+        assert!((keeper.get_std(ts) - variance.sqrt()).abs() < 1e-9);
     }
 
-    /// Gets the SMA keeper (for advanced usage)
-    pub fn get_sma_keeper(&self) -> &SmaKeeper {
-        &self.sma_keeper
+    #[test]
+    fn test_std_and_sma_share_window_with_fast_ticks() {
+        // History capped below the period, which used to split the mean and deviation windows
+        let mut keeper = StdKeeper::new(5, 1000, 3);
+        let mut sampled = Vec::new();
+        let mut last_sample = 0;
+        for i in 1..=40u64 {
+            // Ticks every 300ms, so only some of them land on a 1000ms refresh
+            let ts = i * 300;
+            let mid = 100.0 + (i as f64 * 1.3).sin() * 2.0;
+            keeper.on_receive_tick(ts, mid - 0.1, mid + 0.1);
+            if ts >= last_sample + 1000 {
+                sampled.push(mid);
+                last_sample = ts;
+            }
+        }
+
+        let window = &sampled[sampled.len() - 5..];
+        let mean = window.iter().sum::<f64>() / 5.0;
+        let variance = window.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / 5.0;
+        let (sma, std) = keeper.get_sma_and_std(keeper.get_last_timestamp());
+        assert_eq!(keeper.get_last_timestamp(), last_sample);
+        assert!((sma - mean).abs() < 1e-9);
+        assert!((std - variance.sqrt()).abs() < 1e-9);
     }
 }
@@ -1,16 +1,20 @@
 use std::collections::VecDeque;
 use crate::tick_price_keeper::TickPriceKeeper;
 use crate::common_utils::calculate_volatility_percentage;
+use crate::ema_keeper::EmaKeeper;
 
 /// Keeps track of percentage-based standard deviation (volatility) values, caching them at specified frequency
 pub struct StdPercentageKeeper {
     tick_price_keeper: TickPriceKeeper,
     mid_prices: VecDeque<f64>,
+    mid_ts: VecDeque<u64>,
+    window_ms: Option<u64>,
     frequency_ms: u64,
     cached_std: f64,
     last_cache_timestamp: u64,
     period: usize,
     max_length: usize,
+    smoothing: Option<EmaKeeper>,
 }
 
 impl StdPercentageKeeper {
@@ -21,6 +25,39 @@ impl StdPercentageKeeper {
     /// * `frequency_ms` - Frequency in milliseconds for caching STD
     /// * `max_length` - Maximum length for price history, usually same as the period
     pub fn new(period: usize, frequency_ms: u64, max_length: usize) -> Self {
+        Self::build(period, frequency_ms, max_length, None, None)
+    }
+
+    /// Creates a StdPercentageKeeper measuring volatility over the returns recorded within the
+    /// last `window_ms` milliseconds instead of the last `period` samples. Prices are evicted
+    /// by age only, so any number of samples within the window is kept, and the tick price
+    /// keeper holds just the latest quote.
+    ///
+    /// # Arguments
+    /// * `frequency_ms` - Frequency in milliseconds for recording prices and caching STD
+    /// * `window_ms` - Time window in milliseconds, measured back from the latest recorded tick
+    pub fn with_window_ms(frequency_ms: u64, window_ms: u64) -> Self {
+        Self::build(1, frequency_ms, 1, Some(window_ms), None)
+    }
+
+    /// Creates a StdPercentageKeeper whose output is an EMA over `smoothing_period` of the raw
+    /// volatility, updated once per cached sample. `period` still sets the raw window.
+    pub fn with_smoothing_period(
+        period: usize,
+        frequency_ms: u64,
+        max_length: usize,
+        smoothing_period: usize,
+    ) -> Self {
+        Self::build(period, frequency_ms, max_length, None, Some(smoothing_period))
+    }
+
+    fn build(
+        period: usize,
+        frequency_ms: u64,
+        max_length: usize,
+        window_ms: Option<u64>,
+        smoothing_period: Option<usize>,
+    ) -> Self {
         let max_length = if max_length < period {
             eprintln!("Warning: StdPercentageKeeper max_length ({}) is less than period ({}), setting max_length to period", max_length, period);
             period
@@ -30,12 +67,16 @@ impl StdPercentageKeeper {
         
         StdPercentageKeeper {
             tick_price_keeper: TickPriceKeeper::new(frequency_ms as usize, max_length),
-            mid_prices: VecDeque::with_capacity(max_length),
+            // A time window has no count bound to size the history from
+            mid_prices: VecDeque::with_capacity(if window_ms.is_some() { 0 } else { max_length }),
+            mid_ts: VecDeque::with_capacity(if window_ms.is_some() { 0 } else { max_length }),
+            window_ms,
             frequency_ms,
             cached_std: 0.0,
             last_cache_timestamp: 0,
             period,
             max_length,
+            smoothing: smoothing_period.map(EmaKeeper::new),
         }
     }
 
@@ -56,20 +97,42 @@ impl StdPercentageKeeper {
                 
                 // Store mid price for volatility calculation
                 self.mid_prices.push_back(mid);
+                self.mid_ts.push_back(timestamp);
                 
-                // Maintain max length
+                self.evict_mid_prices(timestamp);
+            }
+            self.update_cache(timestamp);
+            self.last_cache_timestamp = timestamp;
+        }
+    }
+
+    /// Drops the mid prices older than the time window, or beyond max length without one
+    fn evict_mid_prices(&mut self, timestamp: u64) {
+        match self.window_ms {
+            Some(window_ms) => {
+                while let Some(&oldest_ts) = self.mid_ts.front() {
+                    if timestamp.saturating_sub(oldest_ts) <= window_ms {
+                        break;
+                    }
+                    self.mid_prices.pop_front();
+                    self.mid_ts.pop_front();
+                }
+            }
+            None => {
                 while self.mid_prices.len() > self.max_length {
                     self.mid_prices.pop_front();
                 }
+                while self.mid_ts.len() > self.max_length {
+                    self.mid_ts.pop_front();
+                }
             }
-            self.update_cache();
-            self.last_cache_timestamp = timestamp;
         }
     }
 
     /// Gets the current percentage-based standard deviation value (from cache if recent, otherwise recalculates)
+    /// A smoothed value only changes on cached samples, so it is always served from the cache.
     pub fn get_std(&self, timestamp: u64) -> f64 {
-        if timestamp >= self.last_cache_timestamp + self.frequency_ms {
+        if self.smoothing.is_none() && timestamp >= self.last_cache_timestamp + self.frequency_ms {
             // Cache expired, recalculate
             self.calculate_std()
         } else {
@@ -78,9 +141,18 @@ impl StdPercentageKeeper {
         }
     }
 
-    /// Updates the cache with current STD value
-    fn update_cache(&mut self) {
-        self.cached_std = self.calculate_std();
+    /// Gets the unsmoothed volatility of the current history
+    pub fn get_raw_std(&self) -> f64 {
+        self.calculate_std()
+    }
+
+    /// Updates the cache with current STD value, smoothed when a smoothing period is set
+    fn update_cache(&mut self, timestamp: u64) {
+        let raw = self.calculate_std();
+        self.cached_std = match self.smoothing.as_mut() {
+            Some(ema) => ema.add(timestamp, raw),
+            None => raw,
+        };
     }
 
     /// Calculates the percentage-based standard deviation from the mid price history
@@ -89,11 +161,27 @@ impl StdPercentageKeeper {
             return 0.0;
         }
 
-        // Convert VecDeque to Vec for calculate_volatility_percentage
-        let prices: Vec<f64> = self.mid_prices.iter().copied().collect();
-        
-        // Calculate volatility for all prices (handles cases where len < period)
-        let volatilities = calculate_volatility_percentage(&prices, self.period);
+        let volatilities = match self.window_ms {
+            Some(window_ms) => {
+                // Only the prices recorded within the window, measured from the latest tick
+                let latest_ts = self.mid_ts.back().copied().unwrap_or(0);
+                let prices: Vec<f64> = self
+                    .mid_prices
+                    .iter()
+                    .zip(self.mid_ts.iter())
+                    .filter(|(_, ts)| latest_ts.saturating_sub(**ts) <= window_ms)
+                    .map(|(price, _)| *price)
+                    .collect();
+                calculate_volatility_percentage(&prices, prices.len())
+            }
+            None => {
+                // Convert VecDeque to Vec for calculate_volatility_percentage
+                let prices: Vec<f64> = self.mid_prices.iter().copied().collect();
+
+                // Calculate volatility for all prices (handles cases where len < period)
+                calculate_volatility_percentage(&prices, self.period)
+            }
+        };
         
         // Return the last (most recent) volatility value, or 0.0 if None
         volatilities.last()
@@ -111,3 +199,103 @@ impl StdPercentageKeeper {
         self.mid_prices.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_window_excludes_old_returns() {
+        let ticks = [
+            (1000, 100.0),
+            (1200, 120.0),
+            (1300, 90.0),
+            (5000, 100.0),
+            (6000, 100.1),
+            (6500, 100.2),
+            (7000, 100.3),
+        ];
+        let mut sample_keeper = StdPercentageKeeper::new(7, 0, 20);
+        let mut time_keeper = StdPercentageKeeper::with_window_ms(0, 3000);
+        for (ts, mid) in ticks {
+            sample_keeper.on_receive_tick(ts, mid, mid);
+            time_keeper.on_receive_tick(ts, mid, mid);
+        }
+
+        // Only the ticks from 4000 onwards fall inside the window
+        let recent = [100.0, 100.1, 100.2, 100.3];
+        let expected = calculate_volatility_percentage(&recent, recent.len())
+            .last()
+            .and_then(|v| *v)
+            .unwrap();
+        assert!((time_keeper.get_std(7000) - expected).abs() < 1e-12);
+        assert!(time_keeper.get_std(7000) < 1e-4);
+        assert!(sample_keeper.get_std(7000) > 0.05);
+    }
+
+    #[test]
+    fn test_smoothing_period_damps_shock() {
+        let mut raw_keeper = StdPercentageKeeper::new(5, 1000, 5);
+        let mut smooth_keeper = StdPercentageKeeper::with_smoothing_period(5, 1000, 5, 4);
+        let mids = [100.0, 100.1, 100.0, 100.1, 100.0, 100.1, 105.0];
+        for (i, mid) in mids.iter().enumerate() {
+            let ts = (i as u64 + 1) * 1000;
+            raw_keeper.on_receive_tick(ts, *mid, *mid);
+            smooth_keeper.on_receive_tick(ts, *mid, *mid);
+        }
+
+        let ts = raw_keeper.get_last_timestamp();
+        let raw = raw_keeper.get_std(ts);
+        let smoothed = smooth_keeper.get_std(ts);
+        assert_eq!(smooth_keeper.get_raw_std(), raw);
+        assert!(smoothed > 0.0);
+        assert!(smoothed < raw);
+        // Cached smoothed values do not revert to the raw volatility once the cache expires
+        assert_eq!(smooth_keeper.get_std(ts + 10_000), smoothed);
+    }
+
+    #[test]
+    fn test_time_window_keeps_every_sample_in_window() {
+        let mut keeper = StdPercentageKeeper::with_window_ms(100, 1000);
+        let mut mids = Vec::new();
+        for i in 0..=30u64 {
+            let mid = 100.0 + (i as f64 * 0.9).sin();
+            mids.push(mid);
+            keeper.on_receive_tick(1000 + i * 100, mid, mid);
+        }
+
+        // Samples at 3000..=4000ms, 11 of them, all lie within 1000ms of the latest
+        assert_eq!(keeper.get_history_size(), 11);
+        let recent = &mids[mids.len() - 11..];
+        let expected = calculate_volatility_percentage(recent, recent.len())
+            .last()
+            .and_then(|v| *v)
+            .unwrap();
+        assert!((keeper.get_std(4000) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_time_window_keeps_same_millisecond_bursts() {
+        let mut keeper = StdPercentageKeeper::with_window_ms(0, 10);
+        let mut mids = Vec::new();
+        for (i, ts) in [100, 100, 100, 100, 100, 100, 105, 105, 105, 105, 105, 105]
+            .into_iter()
+            .enumerate()
+        {
+            let mid = 100.0 + (i as f64 * 0.7).sin();
+            mids.push(mid);
+            keeper.on_receive_tick(ts, mid, mid);
+        }
+        // Twelve samples in 5ms, more than one per millisecond of the window
+        assert_eq!(keeper.get_history_size(), 12);
+        let expected = calculate_volatility_percentage(&mids, mids.len())
+            .last()
+            .and_then(|v| *v)
+            .unwrap();
+        assert!((keeper.get_std(105) - expected).abs() < 1e-12);
+
+        // The burst at 100ms is more than 10ms older than 111ms
+        keeper.on_receive_tick(111, 100.5, 100.5);
+        assert_eq!(keeper.get_history_size(), 7);
+    }
+}
@@ -1,10 +1,13 @@
 use crate::min_max_keeper::MinMaxKeeper;
-use crate::sma_keeper::SmaKeeper;
+use crate::smoothing::{MovingAverage, Smoothing, SmoothingKeeper};
 
 pub struct StochasticOscillatorKeeper {
-    sma_keeper: SmaKeeper,
+    d_keeper: SmoothingKeeper,
+    smoothing: Smoothing,
     percent_k: f64,
     percent_d: f64,
+    prev_percent_k: f64,
+    prev_percent_d: f64,
     k_period: usize,
     d_period: usize,
     min_max_keeper: MinMaxKeeper,
@@ -12,22 +15,43 @@ pub struct StochasticOscillatorKeeper {
 }
 
 impl StochasticOscillatorKeeper {
-    pub fn new(k_period: usize, d_period: usize) -> Self {
-        StochasticOscillatorKeeper {
+    pub fn new(k_period: usize, d_period: usize) -> Result<Self, String> {
+        Self::with_smoothing(k_period, d_period, Smoothing::Sma)
+    }
+
+    /// Creates a keeper whose %D stage uses the given moving average type
+    pub fn with_smoothing(
+        k_period: usize,
+        d_period: usize,
+        smoothing: Smoothing,
+    ) -> Result<Self, String> {
+        if k_period < 1 {
+            return Err("Stochastic K period at least 1".to_string());
+        }
+        if d_period < 1 {
+            return Err("Stochastic D period at least 1".to_string());
+        }
+
+        Ok(StochasticOscillatorKeeper {
             k_period,
             d_period,
-            sma_keeper: SmaKeeper::new(d_period, 0, 0.0),
+            d_keeper: smoothing.keeper(d_period),
+            smoothing,
             percent_k: 0.0,
             percent_d: 0.0,
-            min_max_keeper: MinMaxKeeper::with_capacity(k_period, 0.0),
+            prev_percent_k: 0.0,
+            prev_percent_d: 0.0,
+            min_max_keeper: MinMaxKeeper::fixed_window(k_period),
             timestamp_counter: 1,
-        }
+        })
     }
 
     pub fn add(&mut self, value: f64) -> Result<(), String> {
         self.min_max_keeper.add(value).map_err(|e| e.to_string())?;
         let highest_high = self.min_max_keeper.get_max();
         let lowest_low = self.min_max_keeper.get_min();
+        self.prev_percent_k = self.percent_k;
+        self.prev_percent_d = self.percent_d;
 
         if (highest_high - lowest_low).abs() > 1e-10 {
             self.percent_k = 100.0 * ((value - lowest_low) / (highest_high - lowest_low));
@@ -35,9 +59,9 @@ impl StochasticOscillatorKeeper {
             self.percent_k = 0.0;
         }
 
-        self.sma_keeper.add(self.timestamp_counter, self.percent_k);
+        self.d_keeper.add(self.timestamp_counter, self.percent_k);
         self.timestamp_counter += 1;
-        self.percent_d = self.sma_keeper.get();
+        self.percent_d = self.d_keeper.get();
 
         Ok(())
     }
@@ -58,6 +82,57 @@ impl StochasticOscillatorKeeper {
         self.percent_d
     }
 
+    /// Gets %K before the latest value
+    pub fn get_prev_k(&self) -> f64 {
+        self.prev_percent_k
+    }
+
+    /// Gets %D before the latest value
+    pub fn get_prev_d(&self) -> f64 {
+        self.prev_percent_d
+    }
+
+    /// Returns 1.0 when %K crosses above %D on the latest value, -1.0 when it crosses below,
+    /// and exactly 0.0 otherwise, following `KdjKeeper::is_cross_golden_death`
+    pub fn is_k_cross_d(&self) -> f64 {
+        if self.timestamp_counter < 3 {
+            return 0.0;
+        }
+        let (k, d) = (self.percent_k, self.percent_d);
+        let (k_prev, d_prev) = (self.prev_percent_k, self.prev_percent_d);
+
+        if k > d && k_prev < d_prev {
+            return 1.0;
+        }
+        if k < d && k_prev > d_prev {
+            return -1.0;
+        }
+        0.0
+    }
+
+    pub fn get_smoothing(&self) -> Smoothing {
+        self.smoothing
+    }
+
+    pub fn get_d_period(&self) -> usize {
+        self.d_period
+    }
+
+    pub fn get_timestamp_counter(&self) -> u64 {
+        self.timestamp_counter
+    }
+
+    /// Clears the rolling window and %K/%D state, keeping the configured periods
+    pub fn reset(&mut self) {
+        self.min_max_keeper.reset();
+        self.d_keeper.reset();
+        self.percent_k = 0.0;
+        self.percent_d = 0.0;
+        self.prev_percent_k = 0.0;
+        self.prev_percent_d = 0.0;
+        self.timestamp_counter = 1;
+    }
+
     pub fn is_overbought(&self) -> bool {
         if self.min_max_keeper.get_len() < self.k_period {
             return false;
@@ -78,19 +153,20 @@ impl StochasticOscillatorKeeper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::assert_reset_matches_fresh;
 
     #[test]
     fn test_stochastic_new() {
-        let keeper = StochasticOscillatorKeeper::new(14, 3);
+        let keeper = StochasticOscillatorKeeper::new(14, 3).unwrap();
         assert_eq!(keeper.k_period, 14);
-        assert_eq!(keeper.d_period, 3);
+        assert_eq!(keeper.get_d_period(), 3);
         assert_eq!(keeper.percent_k, 0.0);
         assert_eq!(keeper.percent_d, 0.0);
     }
 
     #[test]
     fn test_add() {
-        let mut keeper = StochasticOscillatorKeeper::new(14, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(14, 3).unwrap();
         keeper.add(100.0).unwrap();
         keeper.add(101.0).unwrap();
         keeper.add(102.0).unwrap();
@@ -102,7 +178,7 @@ mod tests {
 
     #[test]
     fn test_percent_k_calculation() {
-        let mut keeper = StochasticOscillatorKeeper::new(5, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
         // Add values that create a range
         keeper.add(100.0).unwrap(); // low
         keeper.add(101.0).unwrap();
@@ -115,12 +191,12 @@ mod tests {
         
         // %K should be between 0 and 100
         let k = keeper.get_k();
-        assert!(k >= 0.0 && k <= 100.0);
+        assert!((0.0..=100.0).contains(&k));
     }
 
     #[test]
     fn test_is_overbought() {
-        let mut keeper = StochasticOscillatorKeeper::new(5, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
         // Add enough values to reach k_period
         for i in 0..5 {
             keeper.add(100.0 + i as f64).unwrap();
@@ -130,13 +206,12 @@ mod tests {
         keeper.add(104.0).unwrap();
         
         // May or may not be overbought depending on the calculation
-        let result = keeper.is_overbought();
-        assert!(result || !result); // Just check it doesn't panic
+        let _ = keeper.is_overbought(); // Just check it doesn't panic
     }
 
     #[test]
     fn test_is_oversold() {
-        let mut keeper = StochasticOscillatorKeeper::new(5, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
         // Add enough values to reach k_period
         for i in 0..5 {
             keeper.add(100.0 + i as f64).unwrap();
@@ -146,13 +221,12 @@ mod tests {
         keeper.add(100.0).unwrap();
         
         // May or may not be oversold depending on the calculation
-        let result = keeper.is_oversold();
-        assert!(result || !result); // Just check it doesn't panic
+        let _ = keeper.is_oversold(); // Just check it doesn't panic
     }
 
     #[test]
     fn test_is_overbought_insufficient_data() {
-        let mut keeper = StochasticOscillatorKeeper::new(5, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
         // Not enough data
         keeper.add(100.0).unwrap();
         keeper.add(101.0).unwrap();
@@ -162,7 +236,7 @@ mod tests {
 
     #[test]
     fn test_is_oversold_insufficient_data() {
-        let mut keeper = StochasticOscillatorKeeper::new(5, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
         // Not enough data
         keeper.add(100.0).unwrap();
         keeper.add(101.0).unwrap();
@@ -172,7 +246,7 @@ mod tests {
 
     #[test]
     fn test_get_percent_k_d() {
-        let mut keeper = StochasticOscillatorKeeper::new(14, 3);
+        let mut keeper = StochasticOscillatorKeeper::new(14, 3).unwrap();
         keeper.add(100.0).unwrap();
         keeper.add(101.0).unwrap();
         
@@ -182,5 +256,89 @@ mod tests {
         assert_eq!(k, keeper.get_k());
         assert_eq!(d, keeper.get_d());
     }
-}
 
+    #[test]
+    fn test_reset_timestamp_counter() {
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
+        for i in 0..4 {
+            keeper.add(100.0 + i as f64).unwrap();
+        }
+        assert_eq!(keeper.get_timestamp_counter(), 5);
+
+        keeper.reset();
+        assert_eq!(keeper.get_timestamp_counter(), 1);
+        assert_eq!(keeper.get_k(), 0.0);
+        assert_eq!(keeper.get_d(), 0.0);
+    }
+
+    #[test]
+    fn test_wma_smoothing() {
+        let mut keeper = StochasticOscillatorKeeper::with_smoothing(5, 3, Smoothing::Wma).unwrap();
+        assert_eq!(keeper.get_smoothing(), Smoothing::Wma);
+        keeper.add(100.0).unwrap();
+        keeper.add(104.0).unwrap();
+        keeper.add(102.0).unwrap();
+        // %K = 0, 100, 50 weighted 1, 2, 3
+        assert!((keeper.get_d() - 350.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_prev_lags_by_one() {
+        let mut keeper = StochasticOscillatorKeeper::new(5, 3).unwrap();
+        let mut last = (0.0, 0.0);
+        for price in [100.0, 104.0, 102.0, 101.0, 103.0] {
+            keeper.add(price).unwrap();
+            assert_eq!((keeper.get_prev_k(), keeper.get_prev_d()), last);
+            last = (keeper.get_k(), keeper.get_d());
+        }
+
+        keeper.reset();
+        assert_eq!(keeper.get_prev_k(), 0.0);
+        assert_eq!(keeper.get_prev_d(), 0.0);
+    }
+
+    #[test]
+    fn test_error_on_zero_period() {
+        assert!(StochasticOscillatorKeeper::new(0, 3).is_err());
+        assert!(StochasticOscillatorKeeper::new(14, 0).is_err());
+        assert!(StochasticOscillatorKeeper::with_smoothing(0, 3, Smoothing::Ema).is_err());
+    }
+
+    #[test]
+    fn test_window_retains_k_period() {
+        let mut keeper = StochasticOscillatorKeeper::new(14, 3).unwrap();
+        for i in 0..14 {
+            keeper.add(100.0 + i as f64).unwrap();
+        }
+        assert_eq!(keeper.min_max_keeper.get_len(), 14);
+
+        // 100 drops out, so 106.5 is measured against the 101..113 range
+        keeper.add(106.5).unwrap();
+        assert_eq!(keeper.min_max_keeper.get_len(), 14);
+        assert!((keeper.get_k() - 100.0 * 5.5 / 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reset_matches_fresh() {
+        assert_reset_matches_fresh(
+            || StochasticOscillatorKeeper::new(3, 2).unwrap(),
+            |keeper, _, price| keeper.add(price).unwrap(),
+            |keeper| keeper.reset(),
+            |keeper| (keeper.get_percent_k(), keeper.get_percent_d()),
+        );
+    }
+
+    #[test]
+    fn test_is_k_cross_d() {
+        let mut keeper = StochasticOscillatorKeeper::new(3, 2).unwrap();
+        assert_eq!(keeper.is_k_cross_d(), 0.0);
+
+        // %K = 0, 100, 50, 100, 0, 75, 100 against %D = 0, 50, 75, 75, 50, 37.5, 87.5
+        let prices = [10.0, 12.0, 11.0, 13.0, 9.0, 12.0, 13.0];
+        let expected = [0.0, 0.0, -1.0, 1.0, -1.0, 1.0, 0.0];
+        for (price, cross) in prices.iter().zip(expected) {
+            keeper.add(*price).unwrap();
+            assert_eq!(keeper.is_k_cross_d(), cross, "price {}", price);
+        }
+    }
+}
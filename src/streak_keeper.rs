@@ -0,0 +1,88 @@
+/// Signed count of consecutive up (positive) or down (negative) closes
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreakKeeper {
+    prev_close: Option<f64>,
+    streak: i32,
+}
+
+impl Default for StreakKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreakKeeper {
+    pub fn new() -> Self {
+        StreakKeeper {
+            prev_close: None,
+            streak: 0,
+        }
+    }
+
+    /// Extends the streak on a same-direction move, restarts it at ±1 on a reversal
+    /// and sets it to 0 on an unchanged close
+    pub fn add(&mut self, close: f64) {
+        if let Some(prev_close) = self.prev_close {
+            self.streak = if close > prev_close {
+                if self.streak > 0 {
+                    self.streak + 1
+                } else {
+                    1
+                }
+            } else if close < prev_close {
+                if self.streak < 0 {
+                    self.streak - 1
+                } else {
+                    -1
+                }
+            } else {
+                0
+            };
+        }
+        self.prev_close = Some(close);
+    }
+
+    pub fn get(&self) -> i32 {
+        self.streak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_up_run() {
+        let mut keeper = StreakKeeper::new();
+        keeper.add(100.0);
+        assert_eq!(keeper.get(), 0);
+        keeper.add(101.0);
+        keeper.add(102.0);
+        keeper.add(103.0);
+        assert_eq!(keeper.get(), 3);
+    }
+
+    #[test]
+    fn test_reversal() {
+        let mut keeper = StreakKeeper::new();
+        for close in [100.0, 101.0, 102.0, 101.5] {
+            keeper.add(close);
+        }
+        assert_eq!(keeper.get(), -1);
+        keeper.add(101.0);
+        assert_eq!(keeper.get(), -2);
+        keeper.add(101.2);
+        assert_eq!(keeper.get(), 1);
+    }
+
+    #[test]
+    fn test_flat_bar() {
+        let mut keeper = StreakKeeper::new();
+        for close in [100.0, 99.0, 98.0, 98.0] {
+            keeper.add(close);
+        }
+        assert_eq!(keeper.get(), 0);
+        keeper.add(97.0);
+        assert_eq!(keeper.get(), -1);
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::Debug;
+
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Prices fed to a keeper before it is reset
+const RESET_WARM_UP_PRICES: [f64; 6] = [100.0, 104.0, 98.5, 103.0, 101.0, 99.0];
+
+/// Prices fed to both the reset keeper and a fresh one
+const RESET_PRICES: [f64; 5] = [50.0, 51.5, 50.5, 52.0, 49.0];
+
+/// Prices fed before and after the serde round trip
+#[cfg(feature = "serde")]
+const ROUND_TRIP_PRICES: [f64; 7] = [100.0, 101.5, 99.25, 102.0, 100.75, 103.1, 98.6];
+
+/// Feeds all but the last round-trip price to `keeper` through `feed(keeper, index, price)`,
+/// restores a copy from its JSON state, then feeds the last price to both. Asserts the two
+/// serialize identically and returns `(keeper, restored)` for keeper-specific checks.
+#[cfg(feature = "serde")]
+pub(crate) fn assert_serde_round_trip<K, F>(mut keeper: K, mut feed: F) -> (K, K)
+where
+    K: Serialize + DeserializeOwned,
+    F: FnMut(&mut K, usize, f64),
+{
+    let (last, warm_up) = ROUND_TRIP_PRICES.split_last().unwrap();
+    for (i, price) in warm_up.iter().enumerate() {
+        feed(&mut keeper, i, *price);
+    }
+    let json = serde_json::to_string(&keeper).unwrap();
+    let mut restored: K = serde_json::from_str(&json).unwrap();
+
+    feed(&mut keeper, warm_up.len(), *last);
+    feed(&mut restored, warm_up.len(), *last);
+    assert_eq!(
+        serde_json::to_string(&restored).unwrap(),
+        serde_json::to_string(&keeper).unwrap()
+    );
+    (keeper, restored)
+}
+
+/// Feeds the warm-up prices to a keeper from `make`, resets it through `reset`, then feeds
+/// the same prices to it and to a fresh keeper through `feed(keeper, index, price)`.
+/// Asserts `output` of the two matches right after the reset and after every price.
+pub(crate) fn assert_reset_matches_fresh<K, O, M, F, R, P>(
+    make: M,
+    mut feed: F,
+    reset: R,
+    output: P,
+)
+where
+    M: Fn() -> K,
+    F: FnMut(&mut K, usize, f64),
+    R: FnOnce(&mut K),
+    O: PartialEq + Debug,
+    P: Fn(&K) -> O,
+{
+    let mut keeper = make();
+    for (i, price) in RESET_WARM_UP_PRICES.iter().enumerate() {
+        feed(&mut keeper, i, *price);
+    }
+    reset(&mut keeper);
+
+    let mut fresh = make();
+    assert_eq!(output(&keeper), output(&fresh));
+    for (i, price) in RESET_PRICES.iter().enumerate() {
+        feed(&mut keeper, i, *price);
+        feed(&mut fresh, i, *price);
+        assert_eq!(output(&keeper), output(&fresh), "price index {}", i);
+    }
+}
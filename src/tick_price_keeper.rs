@@ -1,33 +1,54 @@
 use std::collections::VecDeque;
 
+use crate::candle_builder::{Candle, PriceSource};
+
 /// Keeps track of bid and ask prices using sliding windows
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TickPriceKeeper {
     frequency_ms: usize,
     current_bid: f64,
     current_ask: f64,
+    current_bid_size: f64,
+    current_ask_size: f64,
+    prev_spread: f64,
     history_bid: VecDeque<f64>,
     history_ask: VecDeque<f64>,
     history_ts: VecDeque<u64>,
     max_length: usize,
+    record_zero: bool,
+    /// Whether any tick has been received, so `record_zero` never records the initial 0.0
+    has_data: bool,
 }
 
 impl TickPriceKeeper {
     /// Creates a new TickPriceKeeper with the specified frequency and maximum length
     pub fn new(frequency_ms: usize, max_length: usize) -> Self {
+        Self::with_record_zero(frequency_ms, max_length, false)
+    }
+
+    /// Creates a TickPriceKeeper that, when `record_zero` is set, also records zero or
+    /// negative quotes instead of skipping them
+    pub fn with_record_zero(frequency_ms: usize, max_length: usize, record_zero: bool) -> Self {
         TickPriceKeeper {
             frequency_ms,
             current_bid: 0.0,
             current_ask: 0.0,
+            current_bid_size: 0.0,
+            current_ask_size: 0.0,
+            prev_spread: 0.0,
             history_bid: VecDeque::with_capacity(max_length),
             history_ask: VecDeque::with_capacity(max_length),
             history_ts: VecDeque::with_capacity(max_length),
             max_length,
+            record_zero,
+            has_data: false,
         }
     }
 
     /// Called periodically to record the current bid and ask prices
     pub fn on_period_callback(&mut self, timestamp: u64) {
-        if self.current_bid > 0.0 && self.current_ask > 0.0 {
+        let positive = self.current_bid > 0.0 && self.current_ask > 0.0;
+        if self.has_data && (self.record_zero || positive) {
             self.history_bid.push_back(self.current_bid);
             self.history_ask.push_back(self.current_ask);
             self.history_ts.push_back(timestamp);
@@ -47,8 +68,17 @@ impl TickPriceKeeper {
 
     /// Updates the current bid and ask prices
     pub fn on_receive_tick(&mut self, bid: f64, ask: f64) {
+        self.on_receive_tick_with_size(bid, ask, 0.0, 0.0);
+    }
+
+    /// Updates the current bid and ask prices along with their sizes
+    pub fn on_receive_tick_with_size(&mut self, bid: f64, ask: f64, bid_size: f64, ask_size: f64) {
+        self.prev_spread = self.get_current_spread();
         self.current_bid = bid;
         self.current_ask = ask;
+        self.current_bid_size = bid_size;
+        self.current_ask_size = ask_size;
+        self.has_data = true;
     }
 
     /// Gets a history bid price by index (supports negative indexing like Python)
@@ -57,34 +87,40 @@ impl TickPriceKeeper {
     /// * `index` - Index into history (negative values count from the end, -1 is most recent)
     /// 
     /// # Panics
-    /// Panics if history is empty or index is out of range
+    /// Panics if history is empty or index is out of range; see `try_get_history_bid`
     pub fn get_history_bid(&self, index: i64) -> f64 {
+        self.try_get_history_bid(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Gets a history bid price by index like `get_history_bid`, returning an error instead of
+    /// panicking if history is empty or index is out of range
+    pub fn try_get_history_bid(&self, index: i64) -> Result<f64, String> {
         let size = self.history_bid.len();
         
         if size == 0 {
-            panic!("TickPriceKeeper history bid is empty");
+            return Err("TickPriceKeeper history bid is empty".to_string());
         }
 
         let actual_index = if index < 0 {
             let neg_index = (size as i64 + index) as usize;
             if neg_index >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history bid index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             neg_index
         } else {
             if index as usize >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history bid index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             index as usize
         };
 
-        *self.history_bid.get(actual_index).unwrap()
+        Ok(*self.history_bid.get(actual_index).unwrap())
     }
 
     /// Gets a history ask price by index (supports negative indexing like Python)
@@ -93,70 +129,82 @@ impl TickPriceKeeper {
     /// * `index` - Index into history (negative values count from the end, -1 is most recent)
     /// 
     /// # Panics
-    /// Panics if history is empty or index is out of range
+    /// Panics if history is empty or index is out of range; see `try_get_history_ask`
     pub fn get_history_ask(&self, index: i64) -> f64 {
+        self.try_get_history_ask(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Gets a history ask price by index like `get_history_ask`, returning an error instead of
+    /// panicking if history is empty or index is out of range
+    pub fn try_get_history_ask(&self, index: i64) -> Result<f64, String> {
         let size = self.history_ask.len();
         
         if size == 0 {
-            panic!("TickPriceKeeper history ask is empty");
+            return Err("TickPriceKeeper history ask is empty".to_string());
         }
 
         let actual_index = if index < 0 {
             let neg_index = (size as i64 + index) as usize;
             if neg_index >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history ask index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             neg_index
         } else {
             if index as usize >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history ask index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             index as usize
         };
 
-        *self.history_ask.get(actual_index).unwrap()
+        Ok(*self.history_ask.get(actual_index).unwrap())
     }
 
-    /// Gets a history timestamp by index (supports negative indexing)
+    /// Gets a history timestamp by index (supports negative indexing like Python)
     /// 
     /// # Arguments
     /// * `index` - Index into history (negative values count from the end, -1 is most recent)
     /// 
     /// # Panics
-    /// Panics if history is empty or index is out of range
+    /// Panics if history is empty or index is out of range; see `try_get_history_ts`
     pub fn get_history_ts(&self, index: i64) -> u64 {
+        self.try_get_history_ts(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Gets a history timestamp by index like `get_history_ts`, returning an error instead of
+    /// panicking if history is empty or index is out of range
+    pub fn try_get_history_ts(&self, index: i64) -> Result<u64, String> {
         let size = self.history_ts.len();
         
         if size == 0 {
-            panic!("TickPriceKeeper history_ts is empty");
+            return Err("TickPriceKeeper history_ts is empty".to_string());
         }
 
         let actual_index = if index < 0 {
             let neg_index = (size as i64 + index) as usize;
             if neg_index >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history_ts index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             neg_index
         } else {
             if index as usize >= size {
-                panic!(
+                return Err(format!(
                     "TickPriceKeeper history_ts index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             index as usize
         };
 
-        *self.history_ts.get(actual_index).unwrap()
+        Ok(*self.history_ts.get(actual_index).unwrap())
     }
 
     /// Gets the size of the price history
@@ -164,6 +212,64 @@ impl TickPriceKeeper {
         self.history_bid.len()
     }
 
+    /// Gets the recording frequency in milliseconds the keeper was created with
+    pub fn get_frequency_ms(&self) -> usize {
+        self.frequency_ms
+    }
+
+    /// Observed samples per second over the stored history, `(count - 1) / (span_ms / 1000)`,
+    /// so samples recorded every 250ms read 4.0. Returns 0.0 with fewer than 2 samples or
+    /// when they share one timestamp.
+    pub fn sample_rate_hz(&self) -> f64 {
+        let (first, last) = match (self.history_ts.front(), self.history_ts.back()) {
+            (Some(&first), Some(&last)) if last > first => (first, last),
+            _ => return 0.0,
+        };
+        (self.history_ts.len() - 1) as f64 / ((last - first) as f64 / 1000.0)
+    }
+
+    /// Copies the recorded mid prices, oldest first, into an ndarray
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array1<f64> {
+        self.history_bid
+            .iter()
+            .zip(self.history_ask.iter())
+            .map(|(bid, ask)| (bid + ask) / 2.0)
+            .collect()
+    }
+
+    /// Collapses the recorded mids into one bar: open is the oldest mid, close the newest and
+    /// high/low their extremes. Volume is 0.0 as ticks carry none and ts is the oldest recorded
+    /// timestamp. Returns None without history.
+    pub fn summarize_ohlc(&self) -> Option<Candle> {
+        self.summarize_ohlc_by(PriceSource::Mid)
+    }
+
+    /// Like `summarize_ohlc`, on the bids, asks or mids. Quotes carry no last trade price,
+    /// so `PriceSource::Last` uses the mids.
+    pub fn summarize_ohlc_by(&self, source: PriceSource) -> Option<Candle> {
+        let mut prices = self
+            .history_bid
+            .iter()
+            .zip(self.history_ask.iter())
+            .map(|(bid, ask)| source.pick(*bid, *ask, (bid + ask) / 2.0));
+        let open = prices.next()?;
+        let mut candle = Candle {
+            open,
+            high: open,
+            low: open,
+            close: open,
+            volume: 0.0,
+            ts: self.history_ts.front().copied().unwrap_or(0),
+        };
+        for price in prices {
+            candle.high = candle.high.max(price);
+            candle.low = candle.low.min(price);
+            candle.close = price;
+        }
+        Some(candle)
+    }
+
     /// Gets the current bid price
     pub fn get_current_bid(&self) -> f64 {
         self.current_bid
@@ -183,6 +289,18 @@ impl TickPriceKeeper {
         }
     }
 
+    /// Gets the size-weighted micro-price
+    /// `(bid * ask_size + ask * bid_size) / (bid_size + ask_size)`,
+    /// falling back to the simple mid when sizes are unavailable
+    pub fn get_micro_price(&self) -> f64 {
+        let total_size = self.current_bid_size + self.current_ask_size;
+        if total_size <= 0.0 || self.current_bid <= 0.0 || self.current_ask <= 0.0 {
+            return self.get_current_mid();
+        }
+        (self.current_bid * self.current_ask_size + self.current_ask * self.current_bid_size)
+            / total_size
+    }
+
     /// Gets the current spread (ask - bid)
     pub fn get_current_spread(&self) -> f64 {
         if self.current_bid > 0.0 && self.current_ask > 0.0 {
@@ -191,4 +309,174 @@ impl TickPriceKeeper {
             0.0
         }
     }
+
+    /// Gets the spread before the latest tick
+    pub fn get_prev_spread(&self) -> f64 {
+        self.prev_spread
+    }
+
+    /// Current spread minus the previous one; positive when the spread widens
+    pub fn spread_change(&self) -> f64 {
+        self.get_current_spread() - self.prev_spread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
+
+    #[test]
+    fn test_spread_change() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        assert_eq!(keeper.get_frequency_ms(), 1000);
+        keeper.on_receive_tick(100.0, 100.5);
+        keeper.on_receive_tick(99.5, 101.0);
+        assert_eq!(keeper.get_prev_spread(), 0.5);
+        assert_eq!(keeper.get_current_spread(), 1.5);
+        assert_eq!(keeper.spread_change(), 1.0);
+
+        keeper.on_receive_tick(100.0, 100.25);
+        assert_eq!(keeper.spread_change(), -1.25);
+    }
+
+    #[test]
+    fn test_micro_price() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        keeper.on_receive_tick(100.0, 101.0);
+        assert_eq!(keeper.get_micro_price(), 100.5);
+
+        // Heavy bid pushes the fair value toward the ask
+        keeper.on_receive_tick_with_size(100.0, 101.0, 9.0, 1.0);
+        assert!((keeper.get_micro_price() - 100.9).abs() < 1e-9);
+
+        keeper.on_receive_tick_with_size(100.0, 101.0, 1.0, 3.0);
+        assert!((keeper.get_micro_price() - 100.25).abs() < 1e-9);
+
+        keeper.on_receive_tick_with_size(100.0, 101.0, 0.0, 0.0);
+        assert_eq!(keeper.get_micro_price(), 100.5);
+    }
+
+    #[test]
+    fn test_summarize_ohlc() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        assert_eq!(keeper.summarize_ohlc(), None);
+
+        let ticks = [
+            (1000, 99.0, 101.0),
+            (2000, 102.0, 104.0),
+            (3000, 96.0, 98.0),
+            (4000, 100.0, 101.0),
+        ];
+        for (ts, bid, ask) in ticks {
+            keeper.on_receive_tick(bid, ask);
+            keeper.on_period_callback(ts);
+        }
+        let bar = keeper.summarize_ohlc().unwrap();
+        assert_eq!(bar.open, 100.0);
+        assert_eq!(bar.high, 103.0);
+        assert_eq!(bar.low, 97.0);
+        assert_eq!(bar.close, 100.5);
+        assert_eq!(bar.volume, 0.0);
+    }
+
+    #[test]
+    fn test_record_zero() {
+        let mut default_keeper = TickPriceKeeper::new(1000, 10);
+        let mut zero_keeper = TickPriceKeeper::with_record_zero(1000, 10, true);
+        for keeper in [&mut default_keeper, &mut zero_keeper] {
+            keeper.on_receive_tick(-1.5, -0.5);
+            keeper.on_period_callback(1000);
+        }
+
+        assert_eq!(default_keeper.get_history_prices_size(), 0);
+        assert_eq!(zero_keeper.get_history_prices_size(), 1);
+        assert_eq!(zero_keeper.get_history_bid(-1), -1.5);
+        assert_eq!(zero_keeper.get_history_ask(-1), -0.5);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_to_ndarray() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        keeper.on_receive_tick(99.0, 101.0);
+        keeper.on_period_callback(1000);
+        keeper.on_receive_tick(100.0, 102.0);
+        keeper.on_period_callback(2000);
+
+        let array = keeper.to_ndarray();
+        assert_eq!(array.len(), keeper.get_history_prices_size());
+        assert_eq!(array.to_vec(), vec![100.0, 101.0]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = TickPriceKeeper::new(1000, 4);
+        let (keeper, restored) = assert_serde_round_trip(keeper, |keeper, i, price| {
+            keeper.on_receive_tick(price - 0.5, price + 0.5);
+            keeper.on_period_callback((i as u64 + 1) * 1000);
+        });
+        assert_eq!(restored.summarize_ohlc(), keeper.summarize_ohlc());
+    }
+
+    #[test]
+    fn test_summarize_ohlc_by_source() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        for (i, (bid, ask)) in [(99.0, 101.0), (101.0, 105.0), (97.0, 98.0)].iter().enumerate() {
+            keeper.on_receive_tick(*bid, *ask);
+            keeper.on_period_callback((i as u64 + 1) * 1000);
+        }
+        let bid_bar = keeper.summarize_ohlc_by(PriceSource::Bid).unwrap();
+        assert_eq!((bid_bar.open, bid_bar.high, bid_bar.low), (99.0, 101.0, 97.0));
+        let mid_bar = keeper.summarize_ohlc().unwrap();
+        assert_eq!((mid_bar.open, mid_bar.high, mid_bar.low), (100.0, 103.0, 97.5));
+        assert_eq!(keeper.summarize_ohlc_by(PriceSource::Last), Some(mid_bar));
+    }
+
+    #[test]
+    fn test_try_get_history() {
+        let mut keeper = TickPriceKeeper::new(1000, 10);
+        assert!(keeper.try_get_history_bid(-1).is_err());
+        assert!(keeper.try_get_history_ask(0).is_err());
+        assert!(keeper.try_get_history_ts(-1).is_err());
+
+        for (ts, bid, ask) in [(1000, 99.0, 101.0), (2000, 102.0, 104.0)] {
+            keeper.on_receive_tick(bid, ask);
+            keeper.on_period_callback(ts);
+        }
+
+        assert_eq!(keeper.try_get_history_bid(-1), Ok(102.0));
+        assert_eq!(keeper.try_get_history_ask(-2), Ok(101.0));
+        assert_eq!(keeper.try_get_history_ts(-1), Ok(2000));
+        assert_eq!(keeper.get_history_ask(1), 104.0);
+        assert!(keeper.try_get_history_bid(2).is_err());
+        assert!(keeper.try_get_history_ask(2).is_err());
+        assert!(keeper.try_get_history_ts(2).is_err());
+    }
+
+    #[test]
+    fn test_sample_rate_hz() {
+        let mut keeper = TickPriceKeeper::new(500, 100);
+        assert_eq!(keeper.sample_rate_hz(), 0.0);
+        for i in 0..10 {
+            keeper.on_receive_tick(99.0, 101.0);
+            keeper.on_period_callback(1000 + i * 500);
+        }
+        assert!((keeper.sample_rate_hz() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_record_zero_waits_for_first_tick() {
+        let mut keeper = TickPriceKeeper::with_record_zero(1000, 10, true);
+        keeper.on_period_callback(1000);
+        keeper.on_period_callback(2000);
+        assert_eq!(keeper.get_history_prices_size(), 0);
+
+        keeper.on_receive_tick(0.0, 0.0);
+        keeper.on_period_callback(3000);
+        assert_eq!(keeper.get_history_prices_size(), 1);
+        assert_eq!(keeper.get_history_ts(-1), 3000);
+    }
 }
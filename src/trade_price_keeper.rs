@@ -2,54 +2,105 @@ use std::collections::VecDeque;
 use crate::common_utils::BUY;
 
 /// Represents a trade message
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TradeMessage {
     pub price: f64,
     pub side: bool,
+    pub volume: f64,
+}
+
+impl TradeMessage {
+    /// Creates a trade of `volume` at `price` on `side` (`BUY` or `SELL`)
+    pub fn new(price: f64, side: bool, volume: f64) -> Self {
+        TradeMessage { price, side, volume }
+    }
 }
 
 /// Keeps track of trade prices, sides, and timestamps using sliding windows
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TradePriceKeeper {
     frequency_ms: usize,
     current_price: f64,
     current_price_side: bool,
+    current_buy_volume: f64,
+    current_sell_volume: f64,
     history_price: VecDeque<f64>,
     history_sides: VecDeque<f64>,
+    history_buy_volume: VecDeque<f64>,
+    history_sell_volume: VecDeque<f64>,
     history_ts: VecDeque<u64>,
     max_length: usize,
+    record_zero: bool,
+    /// Whether any trade has been received, so `record_zero` never records the initial 0.0
+    has_data: bool,
+    // Running counts of the recorded sides, kept in step with history_sides
+    buy_count: usize,
+    sell_count: usize,
 }
 
 impl TradePriceKeeper {
     /// Creates a new TradePriceKeeper with the specified frequency and maximum length
     pub fn new(frequency_ms: usize, max_length: usize) -> Self {
+        Self::with_record_zero(frequency_ms, max_length, false)
+    }
+
+    /// Creates a TradePriceKeeper that, when `record_zero` is set, also records zero or
+    /// negative prices instead of skipping them
+    pub fn with_record_zero(frequency_ms: usize, max_length: usize, record_zero: bool) -> Self {
         TradePriceKeeper {
             frequency_ms,
             current_price: 0.0,
             current_price_side: BUY,
+            current_buy_volume: 0.0,
+            current_sell_volume: 0.0,
             history_price: VecDeque::with_capacity(max_length),
             history_sides: VecDeque::with_capacity(max_length),
+            history_buy_volume: VecDeque::with_capacity(max_length),
+            history_sell_volume: VecDeque::with_capacity(max_length),
             history_ts: VecDeque::with_capacity(max_length),
             max_length,
+            record_zero,
+            has_data: false,
+            buy_count: 0,
+            sell_count: 0,
         }
     }
 
-    /// Called periodically to record the current price
+    /// Called periodically to record the current price.
+    /// The buy and sell volume traded since the previous record are recorded separately.
     pub fn on_period_callback(&mut self, timestamp: u64) {
-        if self.current_price > 0.0 {
+        if self.has_data && (self.record_zero || self.current_price > 0.0) {
             self.history_price.push_back(self.current_price);
-            self.history_sides.push_back(if self.current_price_side == BUY {
-                1.0
+            if self.current_price_side == BUY {
+                self.history_sides.push_back(1.0);
+                self.buy_count += 1;
             } else {
-                -1.0
-            });
+                self.history_sides.push_back(-1.0);
+                self.sell_count += 1;
+            }
+            self.history_buy_volume.push_back(self.current_buy_volume);
+            self.history_sell_volume.push_back(self.current_sell_volume);
             self.history_ts.push_back(timestamp);
+            self.current_buy_volume = 0.0;
+            self.current_sell_volume = 0.0;
 
             // Maintain max length
             while self.history_price.len() > self.max_length {
                 self.history_price.pop_front();
             }
             while self.history_sides.len() > self.max_length {
-                self.history_sides.pop_front();
+                match self.history_sides.pop_front() {
+                    Some(side) if side > 0.0 => self.buy_count -= 1,
+                    Some(_) => self.sell_count -= 1,
+                    None => {}
+                }
+            }
+            while self.history_buy_volume.len() > self.max_length {
+                self.history_buy_volume.pop_front();
+            }
+            while self.history_sell_volume.len() > self.max_length {
+                self.history_sell_volume.pop_front();
             }
             while self.history_ts.len() > self.max_length {
                 self.history_ts.pop_front();
@@ -57,10 +108,17 @@ impl TradePriceKeeper {
         }
     }
 
-    /// Updates the current price and side from a trade message
+    /// Updates the current price and side from a trade message and accumulates its volume
+    /// on the trade's side
     pub fn on_receive_trade(&mut self, trade: &TradeMessage) {
         self.current_price = trade.price;
         self.current_price_side = trade.side;
+        self.has_data = true;
+        if trade.side == BUY {
+            self.current_buy_volume += trade.volume;
+        } else {
+            self.current_sell_volume += trade.volume;
+        }
     }
 
     /// Gets a history price by index (supports negative indexing like Python)
@@ -69,70 +127,103 @@ impl TradePriceKeeper {
     /// * `index` - Index into history (negative values count from the end, -1 is most recent)
     /// 
     /// # Panics
-    /// Panics if history is empty or index is out of range
+    /// Panics if history is empty or index is out of range; see `try_get_history_price`
     pub fn get_history_price(&self, index: i64) -> f64 {
+        self.try_get_history_price(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Gets a history price by index like `get_history_price`, returning an error instead of
+    /// panicking if history is empty or index is out of range
+    pub fn try_get_history_price(&self, index: i64) -> Result<f64, String> {
         let size = self.history_price.len();
         
         if size == 0 {
-            panic!("TradePriceKeeper history price is empty");
+            return Err("TradePriceKeeper history price is empty".to_string());
         }
 
         let actual_index = if index < 0 {
             let neg_index = (size as i64 + index) as usize;
             if neg_index >= size {
-                panic!(
+                return Err(format!(
                     "TradePriceKeeper history price index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             neg_index
         } else {
             if index as usize >= size {
-                panic!(
+                return Err(format!(
                     "TradePriceKeeper history price index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             index as usize
         };
 
-        *self.history_price.get(actual_index).unwrap()
+        Ok(*self.history_price.get(actual_index).unwrap())
+    }
+
+    /// Gets the buy plus sell volume recorded at `index` (negative values count from the end,
+    /// -1 is most recent), or None when the index is out of range
+    pub fn get_history_volume(&self, index: i64) -> Option<f64> {
+        let size = self.history_buy_volume.len() as i64;
+        let actual_index = if index < 0 { size + index } else { index };
+        if actual_index < 0 || actual_index >= size {
+            return None;
+        }
+        let actual_index = actual_index as usize;
+        Some(self.history_buy_volume[actual_index] + self.history_sell_volume[actual_index])
+    }
+
+    /// Gets all recorded buy plus sell volumes, oldest first
+    pub fn get_volume_series(&self) -> Vec<f64> {
+        self.history_buy_volume
+            .iter()
+            .zip(self.history_sell_volume.iter())
+            .map(|(buy, sell)| buy + sell)
+            .collect()
     }
 
-    /// Gets a history timestamp by index (supports negative indexing)
+    /// Gets a history timestamp by index (supports negative indexing like Python)
     /// 
     /// # Arguments
     /// * `index` - Index into history (negative values count from the end, -1 is most recent)
     /// 
     /// # Panics
-    /// Panics if history is empty or index is out of range
+    /// Panics if history is empty or index is out of range; see `try_get_history_ts`
     pub fn get_history_ts(&self, index: i64) -> u64 {
+        self.try_get_history_ts(index).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Gets a history timestamp by index like `get_history_ts`, returning an error instead of
+    /// panicking if history is empty or index is out of range
+    pub fn try_get_history_ts(&self, index: i64) -> Result<u64, String> {
         let size = self.history_ts.len();
         
         if size == 0 {
-            panic!("TradePriceKeeper history_ts is empty");
+            return Err("TradePriceKeeper history_ts is empty".to_string());
         }
 
         let actual_index = if index < 0 {
             let neg_index = (size as i64 + index) as usize;
             if neg_index >= size {
-                panic!(
+                return Err(format!(
                     "TradePriceKeeper history_ts index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             neg_index
         } else {
             if index as usize >= size {
-                panic!(
+                return Err(format!(
                     "TradePriceKeeper history_ts index out of range index={} size={}",
                     index, size
-                );
+                ));
             }
             index as usize
         };
 
-        *self.history_ts.get(actual_index).unwrap()
+        Ok(*self.history_ts.get(actual_index).unwrap())
     }
 
     /// Gets the size of the price history
@@ -140,11 +231,42 @@ impl TradePriceKeeper {
         self.history_price.len()
     }
 
+    /// Gets the recording frequency in milliseconds the keeper was created with
+    pub fn get_frequency_ms(&self) -> usize {
+        self.frequency_ms
+    }
+
+    /// Observed samples per second over the stored history, `(count - 1) / (span_ms / 1000)`,
+    /// so samples recorded every 250ms read 4.0. Returns 0.0 with fewer than 2 samples or
+    /// when they share one timestamp.
+    pub fn sample_rate_hz(&self) -> f64 {
+        let (first, last) = match (self.history_ts.front(), self.history_ts.back()) {
+            (Some(&first), Some(&last)) if last > first => (first, last),
+            _ => return 0.0,
+        };
+        (self.history_ts.len() - 1) as f64 / ((last - first) as f64 / 1000.0)
+    }
+
+    /// Copies the recorded price history, oldest first, into an ndarray
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array1<f64> {
+        self.history_price.iter().copied().collect()
+    }
+
     /// Gets the current price
     pub fn get_current_price(&self) -> f64 {
         self.current_price
     }
 
+    /// Gets the current price, or None when nothing was recorded within `max_age_ms` of `now`
+    pub fn get_current_price_checked(&self, now: u64, max_age_ms: u64) -> Option<f64> {
+        let last_ts = *self.history_ts.back()?;
+        if now.saturating_sub(last_ts) > max_age_ms {
+            return None;
+        }
+        Some(self.current_price)
+    }
+
     /// Gets the current price side based on recent history (last 10 trades)
     /// Returns 1.0 for buy-dominant, -1.0 for sell-dominant
     pub fn get_current_price_side(&self) -> f64 {
@@ -171,16 +293,127 @@ impl TradePriceKeeper {
         }
     }
 
+    /// Side of the records within `window_ms` of the latest record: 1.0 for buy-dominant,
+    /// -1.0 for sell-dominant, 0.0 when evenly split or there is no history
+    pub fn side_over_ms(&self, window_ms: u64) -> f64 {
+        let latest_ts = match self.history_ts.back() {
+            Some(&ts) => ts,
+            None => return 0.0,
+        };
+
+        let balance: f64 = self
+            .history_sides
+            .iter()
+            .rev()
+            .zip(self.history_ts.iter().rev())
+            .take_while(|(_, ts)| latest_ts.saturating_sub(**ts) <= window_ms)
+            .map(|(side, _)| *side)
+            .sum();
+        if balance > 0.0 {
+            1.0
+        } else if balance < 0.0 {
+            -1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Side of the last `lookback` records with a neutral zone: returns 1.0 when the buy
+    /// fraction is above `neutral_high`, -1.0 when below `neutral_low` and 0.0 otherwise
+    /// or when there is no history
+    pub fn get_price_side_with_band(
+        &self,
+        lookback: usize,
+        neutral_low: f64,
+        neutral_high: f64,
+    ) -> f64 {
+        let count = self.history_sides.len().min(lookback);
+        if count == 0 {
+            return 0.0;
+        }
+
+        let buy_count = self
+            .history_sides
+            .iter()
+            .rev()
+            .take(count)
+            .filter(|side| **side > 0.0)
+            .count();
+        let buy_fraction = buy_count as f64 / count as f64;
+        if buy_fraction > neutral_high {
+            1.0
+        } else if buy_fraction < neutral_low {
+            -1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Fraction of the last `lookback` records on the dominant side, in [0.5, 1.0].
+    /// Returns 0.5 when there is no history.
+    pub fn side_confidence(&self, lookback: usize) -> f64 {
+        let count = self.history_sides.len().min(lookback);
+        if count == 0 {
+            return 0.5;
+        }
+
+        let buy_count = self
+            .history_sides
+            .iter()
+            .rev()
+            .take(count)
+            .filter(|side| **side > 0.0)
+            .count();
+        buy_count.max(count - buy_count) as f64 / count as f64
+    }
+
+    /// Sums the recorded buy volume over the last `lookback` records
+    pub fn buy_volume(&self, lookback: usize) -> f64 {
+        self.side_volume(lookback, true)
+    }
+
+    /// Sums the recorded sell volume over the last `lookback` records
+    pub fn sell_volume(&self, lookback: usize) -> f64 {
+        self.side_volume(lookback, false)
+    }
+
+    fn side_volume(&self, lookback: usize, is_buy: bool) -> f64 {
+        let history = if is_buy {
+            &self.history_buy_volume
+        } else {
+            &self.history_sell_volume
+        };
+        history.iter().rev().take(lookback).sum()
+    }
+
+    /// Gets the side ratio over the whole recorded history in O(1)
+    /// Returns (buy_count - sell_count) / (buy_count + sell_count), 0.0 without history
+    pub fn get_total_side_ratio(&self) -> f64 {
+        let total = self.buy_count + self.sell_count;
+        if total == 0 {
+            return 0.0;
+        }
+        (self.buy_count as f64 - self.sell_count as f64) / total as f64
+    }
+
     /// Gets the side ratio for trades up to a given timestamp
     /// Returns (buy_count - sell_count) / (buy_count + sell_count)
+    /// When `timestamp_to` covers the whole history this is `get_total_side_ratio`, O(1);
+    /// otherwise it walks back from the newest record.
     pub fn get_side_ratio(&self, timestamp_to: u64) -> f64 {
+        match self.history_ts.front() {
+            Some(&oldest) if timestamp_to <= oldest => return self.get_total_side_ratio(),
+            None => return 0.0,
+            _ => {}
+        }
+
         let mut buy_count = 0;
         let mut sell_count = 0;
         
         let size = self.history_sides.len();
         for i in 0..size {
             let idx = -(i as i64 + 1);
-            if let Ok(ts) = self.get_history_ts_safe(idx) {
+            if let Ok(ts) = self.try_get_history_ts(idx) {
                 if ts < timestamp_to {
                     break;
                 }
@@ -225,28 +458,257 @@ impl TradePriceKeeper {
 
         Ok(*self.history_sides.get(actual_index).unwrap())
     }
+}
 
-    /// Helper method to get history timestamp safely
-    fn get_history_ts_safe(&self, index: i64) -> Result<u64, String> {
-        let size = self.history_ts.len();
-        
-        if size == 0 {
-            return Err("history_ts is empty".to_string());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::test_utils::assert_serde_round_trip;
+    use crate::common_utils::SELL;
+
+    fn record(keeper: &mut TradePriceKeeper, timestamp: u64, price: f64, side: bool, volume: f64) {
+        keeper.on_receive_trade(&TradeMessage::new(price, side, volume));
+        keeper.on_period_callback(timestamp);
+    }
+
+    #[test]
+    fn test_buy_sell_volume() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        assert_eq!(keeper.get_frequency_ms(), 1000);
+        record(&mut keeper, 1000, 100.0, BUY, 2.0);
+        record(&mut keeper, 2000, 100.5, SELL, 1.5);
+        record(&mut keeper, 3000, 101.0, BUY, 3.0);
+        record(&mut keeper, 4000, 100.5, SELL, 0.5);
+
+        assert_eq!(keeper.buy_volume(4), 5.0);
+        assert_eq!(keeper.sell_volume(4), 2.0);
+        assert_eq!(keeper.buy_volume(2), 3.0);
+        assert_eq!(keeper.sell_volume(2), 0.5);
+        // Lookback larger than history covers everything
+        assert_eq!(keeper.buy_volume(100), 5.0);
+    }
+
+    #[test]
+    fn test_volume_accumulates_between_records() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        keeper.on_receive_trade(&TradeMessage::new(100.0, BUY, 1.0));
+        keeper.on_receive_trade(&TradeMessage::new(100.1, BUY, 2.0));
+        keeper.on_period_callback(1000);
+        // No trades in this period
+        keeper.on_period_callback(2000);
+
+        assert_eq!(keeper.buy_volume(1), 0.0);
+        assert_eq!(keeper.buy_volume(2), 3.0);
+    }
+
+    #[test]
+    fn test_get_current_price_checked() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        assert_eq!(keeper.get_current_price_checked(1000, 5000), None);
+
+        record(&mut keeper, 1000, 100.0, BUY, 1.0);
+        assert_eq!(keeper.get_current_price_checked(3000, 5000), Some(100.0));
+        assert_eq!(keeper.get_current_price_checked(6000, 5000), Some(100.0));
+        assert_eq!(keeper.get_current_price_checked(6001, 5000), None);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_to_ndarray() {
+        let mut keeper = TradePriceKeeper::new(1000, 2);
+        record(&mut keeper, 1000, 100.0, BUY, 1.0);
+        record(&mut keeper, 2000, 101.0, BUY, 1.0);
+        record(&mut keeper, 3000, 102.0, SELL, 1.0);
+
+        let array = keeper.to_ndarray();
+        assert_eq!(array.len(), keeper.get_history_prices_size());
+        assert_eq!(array.to_vec(), vec![101.0, 102.0]);
+    }
+
+    #[test]
+    fn test_price_side_with_band() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        assert_eq!(keeper.get_price_side_with_band(10, 0.45, 0.55), 0.0);
+
+        for i in 0..10 {
+            let side = if i % 2 == 0 { BUY } else { SELL };
+            record(&mut keeper, (i + 1) * 1000, 100.0, side, 1.0);
         }
+        // Even split stays neutral where the majority rule would call it a sell
+        assert_eq!(keeper.get_price_side_with_band(10, 0.45, 0.55), 0.0);
+        assert_eq!(keeper.get_current_price_side(), -1.0);
 
-        let actual_index = if index < 0 {
-            let neg_index = (size as i64 + index) as usize;
-            if neg_index >= size {
-                return Err(format!("index out of range: {}", index));
-            }
-            neg_index
-        } else {
-            if index as usize >= size {
-                return Err(format!("index out of range: {}", index));
-            }
-            index as usize
+        record(&mut keeper, 11000, 100.0, BUY, 1.0);
+        record(&mut keeper, 12000, 100.0, BUY, 1.0);
+        // Last 10 records hold 6 buys
+        assert_eq!(keeper.get_price_side_with_band(10, 0.45, 0.55), 1.0);
+        assert_eq!(keeper.get_price_side_with_band(4, 0.45, 0.55), 1.0);
+        assert_eq!(keeper.get_price_side_with_band(10, 0.45, 0.65), 0.0);
+    }
+
+    #[test]
+    fn test_side_confidence() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        assert_eq!(keeper.side_confidence(10), 0.5);
+
+        for i in 0..10 {
+            let side = if i < 8 { BUY } else { SELL };
+            record(&mut keeper, (i + 1) * 1000, 100.0, side, 1.0);
+        }
+        assert!((keeper.side_confidence(10) - 0.8).abs() < 1e-12);
+        // Only the two most recent sells
+        assert_eq!(keeper.side_confidence(2), 1.0);
+        assert_eq!(keeper.side_confidence(4), 0.5);
+    }
+
+    #[test]
+    fn test_record_zero() {
+        let mut default_keeper = TradePriceKeeper::new(1000, 10);
+        let mut zero_keeper = TradePriceKeeper::with_record_zero(1000, 10, true);
+        for keeper in [&mut default_keeper, &mut zero_keeper] {
+            record(keeper, 1000, -2.0, SELL, 1.0);
+        }
+
+        assert_eq!(default_keeper.get_history_prices_size(), 0);
+        assert_eq!(zero_keeper.get_history_prices_size(), 1);
+        assert_eq!(zero_keeper.get_history_price(-1), -2.0);
+    }
+
+    #[test]
+    fn test_trade_message_eq() {
+        let trade = TradeMessage {
+            price: 100.5,
+            side: BUY,
+            volume: 2.0,
         };
+        assert_eq!(trade, trade.clone());
+        assert_ne!(trade, TradeMessage { side: SELL, ..trade.clone() });
+    }
 
-        Ok(*self.history_ts.get(actual_index).unwrap())
+    #[test]
+    fn test_side_over_ms() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        assert_eq!(keeper.side_over_ms(5000), 0.0);
+
+        // Heavy selling a minute ago, light buying in the last few seconds
+        for i in 0..8 {
+            record(&mut keeper, 1000 + i * 1000, 100.0, SELL, 1.0);
+        }
+        for i in 0..3 {
+            record(&mut keeper, 60000 + i * 1000, 101.0, BUY, 1.0);
+        }
+
+        assert_eq!(keeper.side_over_ms(5000), 1.0);
+        assert_eq!(keeper.get_current_price_side(), -1.0);
+        assert_eq!(keeper.side_over_ms(60000), -1.0);
+    }
+
+    #[test]
+    fn test_history_volume() {
+        let mut keeper = TradePriceKeeper::new(1000, 3);
+        assert_eq!(keeper.get_history_volume(-1), None);
+
+        record(&mut keeper, 1000, 100.0, BUY, 2.0);
+        record(&mut keeper, 2000, 100.5, SELL, 1.5);
+        record(&mut keeper, 3000, 101.0, BUY, 3.0);
+        record(&mut keeper, 4000, 100.5, SELL, 0.5);
+
+        assert_eq!(keeper.get_volume_series(), vec![1.5, 3.0, 0.5]);
+        assert_eq!(keeper.get_history_volume(-1), Some(0.5));
+        assert_eq!(keeper.get_history_volume(-3), Some(1.5));
+        assert_eq!(keeper.get_history_volume(0), Some(1.5));
+        assert_eq!(keeper.get_history_volume(2), Some(0.5));
+        assert_eq!(keeper.get_history_volume(3), None);
+        assert_eq!(keeper.get_history_volume(-4), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_state_round_trip() {
+        let keeper = TradePriceKeeper::new(1000, 4);
+        let (keeper, restored) = assert_serde_round_trip(keeper, |keeper, i, price| {
+            keeper.on_receive_trade(&TradeMessage::new(price, i % 2 == 0, 1.5));
+            keeper.on_period_callback((i as u64 + 1) * 1000);
+        });
+        assert_eq!(restored.get_volume_series(), keeper.get_volume_series());
+    }
+
+    #[test]
+    fn test_total_side_ratio_matches_loop() {
+        let mut keeper = TradePriceKeeper::new(1000, 5);
+        assert_eq!(keeper.get_total_side_ratio(), 0.0);
+        let sides = [BUY, BUY, SELL, BUY, SELL, SELL, SELL, BUY];
+        for (i, side) in sides.iter().enumerate() {
+            keeper.on_receive_trade(&TradeMessage::new(100.0, *side, 1.0));
+            keeper.on_period_callback((i as u64 + 1) * 1000);
+
+            let sides = &keeper.history_sides;
+            let expected = sides.iter().sum::<f64>() / sides.len() as f64;
+            assert!((keeper.get_total_side_ratio() - expected).abs() < 1e-12);
+            assert_eq!(keeper.get_side_ratio(0), keeper.get_total_side_ratio());
+        }
+        // The last five records are BUY, SELL, SELL, SELL, BUY after evictions
+        assert!((keeper.get_total_side_ratio() + 0.2).abs() < 1e-12);
+        // A bounded window still walks the newest records
+        assert_eq!(keeper.get_side_ratio(7000), 0.0);
+    }
+
+    #[test]
+    fn test_try_get_history() {
+        let mut keeper = TradePriceKeeper::new(1000, 3);
+        assert!(keeper.try_get_history_price(-1).is_err());
+        assert!(keeper.try_get_history_ts(0).is_err());
+
+        record(&mut keeper, 1000, 100.0, BUY, 2.0);
+        record(&mut keeper, 2000, 100.5, SELL, 1.5);
+        record(&mut keeper, 3000, 101.0, BUY, 3.0);
+
+        assert_eq!(keeper.try_get_history_price(-1), Ok(101.0));
+        assert_eq!(keeper.try_get_history_price(-3), Ok(100.0));
+        assert_eq!(keeper.try_get_history_ts(-2), Ok(2000));
+        assert_eq!(keeper.get_history_price(-1), 101.0);
+        assert_eq!(keeper.get_history_ts(0), 1000);
+        assert!(keeper.try_get_history_price(3).is_err());
+        assert!(keeper.try_get_history_ts(3).is_err());
+        assert!(keeper.try_get_history_price(-4).is_err());
+    }
+
+    #[test]
+    fn test_sample_rate_hz() {
+        let mut keeper = TradePriceKeeper::new(250, 100);
+        assert_eq!(keeper.sample_rate_hz(), 0.0);
+        record(&mut keeper, 1000, 100.0, BUY, 1.0);
+        assert_eq!(keeper.sample_rate_hz(), 0.0);
+
+        for i in 1..=8 {
+            record(&mut keeper, 1000 + i * 250, 100.0, BUY, 1.0);
+        }
+        assert!((keeper.sample_rate_hz() - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_mixed_sides_in_one_period() {
+        let mut keeper = TradePriceKeeper::new(1000, 100);
+        keeper.on_receive_trade(&TradeMessage::new(100.0, BUY, 5.0));
+        keeper.on_receive_trade(&TradeMessage::new(99.9, SELL, 1.0));
+        keeper.on_period_callback(1000);
+
+        assert_eq!(keeper.buy_volume(1), 5.0);
+        assert_eq!(keeper.sell_volume(1), 1.0);
+        assert_eq!(keeper.get_history_volume(-1), Some(6.0));
+        assert_eq!(keeper.get_volume_series(), vec![6.0]);
+    }
+
+    #[test]
+    fn test_record_zero_waits_for_first_trade() {
+        let mut keeper = TradePriceKeeper::with_record_zero(1000, 10, true);
+        keeper.on_period_callback(1000);
+        keeper.on_period_callback(2000);
+        assert_eq!(keeper.get_history_prices_size(), 0);
+
+        record(&mut keeper, 3000, 0.0, SELL, 1.0);
+        assert_eq!(keeper.get_history_prices_size(), 1);
+        assert_eq!(keeper.get_history_ts(-1), 3000);
     }
 }
@@ -0,0 +1,128 @@
+use crate::ema_keeper::EmaKeeper;
+
+/// True Strength Index: `100 * EMA_short(EMA_long(momentum)) / EMA_short(EMA_long(|momentum|))`,
+/// bounded to -100..100, with an optional EMA signal line
+pub struct TsiKeeper {
+    momentum_long: EmaKeeper,
+    momentum_short: EmaKeeper,
+    abs_momentum_long: EmaKeeper,
+    abs_momentum_short: EmaKeeper,
+    signal: Option<EmaKeeper>,
+    prev_price: Option<f64>,
+    tsi: f64,
+    timestamp_counter: u64,
+}
+
+impl TsiKeeper {
+    pub fn new(long_period: usize, short_period: usize) -> Result<Self, String> {
+        Self::build(long_period, short_period, None)
+    }
+
+    /// Creates a keeper that also tracks an EMA of the TSI over `signal_period`
+    pub fn with_signal(
+        long_period: usize,
+        short_period: usize,
+        signal_period: usize,
+    ) -> Result<Self, String> {
+        if signal_period < 1 {
+            return Err("TSI signal period at least 1".to_string());
+        }
+        Self::build(long_period, short_period, Some(signal_period))
+    }
+
+    fn build(
+        long_period: usize,
+        short_period: usize,
+        signal_period: Option<usize>,
+    ) -> Result<Self, String> {
+        if long_period < 1 || short_period < 1 {
+            return Err("TSI periods at least 1".to_string());
+        }
+
+        Ok(TsiKeeper {
+            momentum_long: EmaKeeper::new(long_period),
+            momentum_short: EmaKeeper::new(short_period),
+            abs_momentum_long: EmaKeeper::new(long_period),
+            abs_momentum_short: EmaKeeper::new(short_period),
+            signal: signal_period.map(EmaKeeper::new),
+            prev_price: None,
+            tsi: 0.0,
+            timestamp_counter: 1,
+        })
+    }
+
+    /// Adds a price. The first price only seeds the momentum, so TSI stays 0.0.
+    pub fn add(&mut self, price: f64) {
+        let prev_price = match self.prev_price.replace(price) {
+            Some(prev_price) => prev_price,
+            None => return,
+        };
+
+        let momentum = price - prev_price;
+        let ts = self.timestamp_counter;
+        let smoothed = self.momentum_long.add(ts, momentum);
+        let smoothed = self.momentum_short.add(ts, smoothed);
+        let abs_smoothed = self.abs_momentum_long.add(ts, momentum.abs());
+        let abs_smoothed = self.abs_momentum_short.add(ts, abs_smoothed);
+        self.timestamp_counter += 1;
+
+        self.tsi = if abs_smoothed == 0.0 { 0.0 } else { 100.0 * smoothed / abs_smoothed };
+        if let Some(signal) = self.signal.as_mut() {
+            signal.add(ts, self.tsi);
+        }
+    }
+
+    pub fn get(&self) -> f64 {
+        self.tsi
+    }
+
+    /// Gets the signal line, None without a signal period
+    pub fn get_signal(&self) -> Option<f64> {
+        self.signal.as_ref().map(|signal| signal.get())
+    }
+
+    pub fn get_timestamp_counter(&self) -> u64 {
+        self.timestamp_counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tsi_new() {
+        assert!(TsiKeeper::new(25, 13).is_ok());
+        assert!(TsiKeeper::new(0, 13).is_err());
+        assert!(TsiKeeper::with_signal(25, 13, 0).is_err());
+    }
+
+    #[test]
+    fn test_strong_uptrend() {
+        let mut keeper = TsiKeeper::with_signal(25, 13, 7).unwrap();
+        keeper.add(100.0);
+        assert_eq!(keeper.get(), 0.0);
+
+        for i in 1..60 {
+            // Rising with an occasional small pullback
+            let price = 100.0 + i as f64 - if i % 5 == 0 { 1.5 } else { 0.0 };
+            keeper.add(price);
+        }
+        assert!(keeper.get() > 50.0);
+        assert!(keeper.get() <= 100.0);
+        assert!(keeper.get_signal().unwrap() > 50.0);
+    }
+
+    #[test]
+    fn test_monotonic_moves_saturate() {
+        let mut up = TsiKeeper::new(5, 3).unwrap();
+        let mut down = TsiKeeper::new(5, 3).unwrap();
+        for i in 0..10 {
+            up.add(100.0 + i as f64);
+            down.add(100.0 - i as f64);
+        }
+        assert!((up.get() - 100.0).abs() < 1e-9);
+        assert!((down.get() + 100.0).abs() < 1e-9);
+        assert_eq!(up.get_signal(), None);
+    }
+}
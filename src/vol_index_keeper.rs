@@ -0,0 +1,95 @@
+/// Starting value of both volume indexes
+pub const VOL_INDEX_SEED: f64 = 1000.0;
+
+/// Negative and Positive Volume Index: cumulative percent price change counted only on bars
+/// where volume fell (NVI) or rose (PVI) from the previous bar
+pub struct VolIndexKeeper {
+    prev_close: Option<f64>,
+    prev_volume: f64,
+    nvi: f64,
+    pvi: f64,
+}
+
+impl Default for VolIndexKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VolIndexKeeper {
+    pub fn new() -> Self {
+        VolIndexKeeper {
+            prev_close: None,
+            prev_volume: 0.0,
+            nvi: VOL_INDEX_SEED,
+            pvi: VOL_INDEX_SEED,
+        }
+    }
+
+    /// Adds a bar. The first bar only sets the previous close and volume; a bar with
+    /// unchanged volume, or following a zero close, moves neither index.
+    pub fn add(&mut self, close: f64, volume: f64) {
+        let prev_volume = std::mem::replace(&mut self.prev_volume, volume);
+        let prev_close = match self.prev_close.replace(close) {
+            Some(prev_close) if prev_close.abs() > 1e-10 => prev_close,
+            _ => return,
+        };
+
+        let change = (close - prev_close) / prev_close;
+        if volume < prev_volume {
+            self.nvi *= 1.0 + change;
+        } else if volume > prev_volume {
+            self.pvi *= 1.0 + change;
+        }
+    }
+
+    /// Gets the Negative Volume Index
+    pub fn get_nvi(&self) -> f64 {
+        self.nvi
+    }
+
+    /// Gets the Positive Volume Index
+    pub fn get_pvi(&self) -> f64 {
+        self.pvi
+    }
+
+    /// Restores both indexes to the seed and forgets the previous bar
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_index_moves_on_its_own_bars() {
+        let mut keeper = VolIndexKeeper::new();
+        keeper.add(100.0, 500.0);
+        assert_eq!((keeper.get_nvi(), keeper.get_pvi()), (1000.0, 1000.0));
+
+        // Volume rises: only PVI moves, +10%
+        keeper.add(110.0, 800.0);
+        assert!((keeper.get_pvi() - 1100.0).abs() < 1e-9);
+        assert_eq!(keeper.get_nvi(), 1000.0);
+
+        // Volume falls: only NVI moves, -10%
+        keeper.add(99.0, 300.0);
+        assert!((keeper.get_nvi() - 900.0).abs() < 1e-9);
+        assert!((keeper.get_pvi() - 1100.0).abs() < 1e-9);
+
+        // Volume rises again: PVI takes the +20%
+        keeper.add(118.8, 900.0);
+        assert!((keeper.get_pvi() - 1320.0).abs() < 1e-9);
+        assert!((keeper.get_nvi() - 900.0).abs() < 1e-9);
+
+        // Unchanged volume moves neither
+        keeper.add(200.0, 900.0);
+        assert!((keeper.get_pvi() - 1320.0).abs() < 1e-9);
+        assert!((keeper.get_nvi() - 900.0).abs() < 1e-9);
+
+        keeper.reset();
+        assert_eq!((keeper.get_nvi(), keeper.get_pvi()), (VOL_INDEX_SEED, VOL_INDEX_SEED));
+    }
+}
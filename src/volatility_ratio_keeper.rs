@@ -0,0 +1,87 @@
+use crate::std_percentage_keeper::StdPercentageKeeper;
+
+/// Ratio of short-window to long-window return volatility, above 1.0 when volatility expands
+pub struct VolatilityRatioKeeper {
+    short_std: StdPercentageKeeper,
+    long_std: StdPercentageKeeper,
+}
+
+impl VolatilityRatioKeeper {
+    /// Creates a keeper comparing the last `short_period` mids to the last `long_period` mids,
+    /// sampled at most once every `frequency_ms`
+    pub fn new(short_period: usize, long_period: usize, frequency_ms: u64) -> Result<Self, String> {
+        if short_period < 2 {
+            return Err("Volatility short period at least 2".to_string());
+        }
+        if long_period <= short_period {
+            return Err("Volatility long period must exceed short period".to_string());
+        }
+
+        Ok(VolatilityRatioKeeper {
+            short_std: StdPercentageKeeper::new(short_period, frequency_ms, short_period),
+            long_std: StdPercentageKeeper::new(long_period, frequency_ms, long_period),
+        })
+    }
+
+    pub fn on_receive_tick(&mut self, timestamp: u64, bid: f64, ask: f64) {
+        self.short_std.on_receive_tick(timestamp, bid, ask);
+        self.long_std.on_receive_tick(timestamp, bid, ask);
+    }
+
+    pub fn get_short_vol(&self) -> f64 {
+        self.short_std.get_std(self.short_std.get_last_timestamp())
+    }
+
+    pub fn get_long_vol(&self) -> f64 {
+        self.long_std.get_std(self.long_std.get_last_timestamp())
+    }
+
+    /// Returns short volatility / long volatility, or 1.0 while the long volatility is zero
+    pub fn get_ratio(&self) -> f64 {
+        let long = self.get_long_vol();
+        if long == 0.0 {
+            return 1.0;
+        }
+        self.get_short_vol() / long
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volatility_ratio_new() {
+        assert!(VolatilityRatioKeeper::new(5, 20, 1000).is_ok());
+        assert!(VolatilityRatioKeeper::new(1, 20, 1000).is_err());
+        assert!(VolatilityRatioKeeper::new(20, 20, 1000).is_err());
+    }
+
+    #[test]
+    fn test_zero_long_vol_is_neutral() {
+        let mut keeper = VolatilityRatioKeeper::new(3, 6, 1000).unwrap();
+        for i in 0..10 {
+            keeper.on_receive_tick(i * 1000, 100.0, 100.0);
+        }
+        assert_eq!(keeper.get_long_vol(), 0.0);
+        assert_eq!(keeper.get_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_burst_expands_ratio() {
+        let mut keeper = VolatilityRatioKeeper::new(5, 20, 1000).unwrap();
+        let mut ts = 0;
+        for i in 0..20 {
+            let mid = if i % 2 == 0 { 100.0 } else { 100.1 };
+            keeper.on_receive_tick(ts, mid, mid);
+            ts += 1000;
+        }
+        assert!((keeper.get_ratio() - 1.0).abs() < 0.1);
+
+        for mid in [103.0, 98.0, 104.0, 97.0, 103.0] {
+            keeper.on_receive_tick(ts, mid, mid);
+            ts += 1000;
+        }
+        assert!(keeper.get_ratio() > 1.5);
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::VecDeque;
+
+use crate::indicator::OhlcIndicator;
+use crate::trade_price_keeper::TradeMessage;
+
+/// Volume-weighted average price with its volume-weighted standard deviation, either over the
+/// whole session or over a sliding time window
+pub struct VwapKeeper {
+    sum_volume: f64,
+    // Volume-weighted Welford moments: the VWAP and the weighted sum of squared deviations
+    mean: f64,
+    m2: f64,
+    // Trades evicted since the moments were last recomputed from `entries`
+    evictions: usize,
+    last_timestamp: u64,
+    window_ms: Option<u64>,
+    // (price, volume, timestamp), kept only in windowed mode
+    entries: VecDeque<(f64, f64, u64)>,
+}
+
+impl Default for VwapKeeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VwapKeeper {
+    pub fn new() -> Self {
+        Self::build(None)
+    }
+
+    /// Creates a keeper over the trades of the last `window_ms` milliseconds,
+    /// measured back from the latest timestamp
+    pub fn with_window_ms(window_ms: u64) -> Self {
+        Self::build(Some(window_ms))
+    }
+
+    fn build(window_ms: Option<u64>) -> Self {
+        VwapKeeper {
+            sum_volume: 0.0,
+            mean: 0.0,
+            m2: 0.0,
+            evictions: 0,
+            last_timestamp: 0,
+            window_ms,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn add(&mut self, price: f64, volume: f64, timestamp: u64) {
+        self.push_moments(price, volume);
+        self.last_timestamp = timestamp;
+
+        if let Some(window_ms) = self.window_ms {
+            self.entries.push_back((price, volume, timestamp));
+            while let Some(&(old_price, old_volume, old_ts)) = self.entries.front() {
+                if timestamp.saturating_sub(old_ts) <= window_ms {
+                    break;
+                }
+                self.pop_moments(old_price, old_volume);
+                self.entries.pop_front();
+                self.evictions += 1;
+            }
+
+            // Removing trades accumulates rounding error; recompute once per window turnover
+            if self.evictions >= self.entries.len().max(1) {
+                self.resync_moments();
+            }
+        }
+    }
+
+    /// Weighted Welford update for a trade entering the VWAP
+    fn push_moments(&mut self, price: f64, volume: f64) {
+        let sum_volume = self.sum_volume + volume;
+        if sum_volume <= 0.0 {
+            self.clear_moments();
+            return;
+        }
+        let delta = price - self.mean;
+        self.mean += delta * volume / sum_volume;
+        self.m2 += volume * delta * (price - self.mean);
+        self.sum_volume = sum_volume;
+    }
+
+    /// Reverses `push_moments` for a trade leaving the window
+    fn pop_moments(&mut self, price: f64, volume: f64) {
+        let sum_volume = self.sum_volume - volume;
+        if sum_volume <= 0.0 {
+            self.clear_moments();
+            return;
+        }
+        let delta = price - self.mean;
+        self.mean -= delta * volume / sum_volume;
+        self.m2 = (self.m2 - volume * delta * (price - self.mean)).max(0.0);
+        self.sum_volume = sum_volume;
+    }
+
+    /// Recomputes the moments of the windowed trades in two passes
+    fn resync_moments(&mut self) {
+        self.evictions = 0;
+        self.sum_volume = self.entries.iter().map(|&(_, volume, _)| volume).sum();
+        if self.sum_volume <= 0.0 {
+            self.clear_moments();
+            return;
+        }
+        let mean = self.entries.iter().map(|&(price, volume, _)| price * volume).sum::<f64>()
+            / self.sum_volume;
+        self.mean = mean;
+        self.m2 = self
+            .entries
+            .iter()
+            .map(|&(price, volume, _)| volume * (price - mean) * (price - mean))
+            .sum();
+    }
+
+    fn clear_moments(&mut self) {
+        self.sum_volume = 0.0;
+        self.mean = 0.0;
+        self.m2 = 0.0;
+    }
+
+    /// Adds a trade received at `timestamp`
+    pub fn add_trade(&mut self, trade: &TradeMessage, timestamp: u64) {
+        self.add(trade.price, trade.volume, timestamp);
+    }
+
+    /// Starts a new session at `timestamp`, e.g. for a daily VWAP, discarding all prior trades
+    pub fn reset_session(&mut self, timestamp: u64) {
+        self.clear_moments();
+        self.evictions = 0;
+        self.last_timestamp = timestamp;
+        self.entries.clear();
+    }
+
+    pub fn get_window_ms(&self) -> Option<u64> {
+        self.window_ms
+    }
+
+    pub fn get_last_timestamp(&self) -> u64 {
+        self.last_timestamp
+    }
+
+    /// Gets the VWAP, 0.0 when no volume has been added
+    pub fn get(&self) -> f64 {
+        if self.sum_volume <= 0.0 {
+            return 0.0;
+        }
+        self.mean
+    }
+
+    /// Gets the volume-weighted standard deviation of price around the VWAP
+    pub fn get_std(&self) -> f64 {
+        if self.sum_volume <= 0.0 {
+            return 0.0;
+        }
+        (self.m2 / self.sum_volume).sqrt()
+    }
+
+    /// Z-score of `price` against the VWAP, `(price - vwap) / vwap_std`, 0.0 when the std is zero
+    pub fn zscore(&self, price: f64) -> f64 {
+        let std = self.get_std();
+        if std == 0.0 {
+            return 0.0;
+        }
+        (price - self.get()) / std
+    }
+}
+
+impl OhlcIndicator for VwapKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        volume: f64,
+        ts: u64,
+    ) -> Result<(), String> {
+        // Bars are weighted at their typical price
+        self.add((high + low + close) / 3.0, volume, ts);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_vwap() {
+        let keeper = VwapKeeper::new();
+        assert_eq!(keeper.get(), 0.0);
+        assert_eq!(keeper.get_std(), 0.0);
+        assert_eq!(keeper.zscore(100.0), 0.0);
+    }
+
+    #[test]
+    fn test_vwap() {
+        let mut keeper = VwapKeeper::new();
+        keeper.add(100.0, 1.0, 1000);
+        keeper.add(103.0, 2.0, 2000);
+        assert!((keeper.get() - 102.0).abs() < 1e-9);
+        assert_eq!(keeper.get_last_timestamp(), 2000);
+    }
+
+    #[test]
+    fn test_zscore() {
+        let mut keeper = VwapKeeper::new();
+        keeper.add(99.0, 5.0, 1000);
+        keeper.add(101.0, 5.0, 2000);
+        assert!((keeper.get() - 100.0).abs() < 1e-9);
+        assert!((keeper.get_std() - 1.0).abs() < 1e-9);
+
+        // One vwap-std above VWAP
+        assert!((keeper.zscore(101.0) - 1.0).abs() < 1e-9);
+        assert!((keeper.zscore(98.0) + 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zscore_zero_std() {
+        let mut keeper = VwapKeeper::new();
+        keeper.add(100.0, 3.0, 1000);
+        keeper.add(100.0, 1.0, 2000);
+        assert_eq!(keeper.zscore(105.0), 0.0);
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let mut keeper = VwapKeeper::with_window_ms(2000);
+        keeper.add(100.0, 4.0, 1000);
+        keeper.add(110.0, 1.0, 2000);
+        keeper.add(104.0, 1.0, 3000);
+        assert!((keeper.get() - 614.0 / 6.0).abs() < 1e-9);
+
+        // The trade at 1000 falls out of the window
+        keeper.add(108.0, 2.0, 3500);
+        assert!((keeper.get() - (110.0 + 104.0 + 216.0) / 4.0).abs() < 1e-9);
+        assert_eq!(keeper.get_window_ms(), Some(2000));
+    }
+
+    #[test]
+    fn test_reset_session() {
+        let mut keeper = VwapKeeper::new();
+        keeper.add_trade(&TradeMessage::new(100.0, true, 3.0), 1000);
+        keeper.reset_session(86_400_000);
+        assert_eq!(keeper.get(), 0.0);
+        assert_eq!(keeper.get_last_timestamp(), 86_400_000);
+
+        keeper.add_trade(&TradeMessage::new(90.0, false, 1.0), 86_401_000);
+        keeper.add(96.0, 2.0, 86_402_000);
+        assert!((keeper.get() - 94.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_volume() {
+        let mut keeper = VwapKeeper::with_window_ms(1000);
+        keeper.add(100.0, 0.0, 1000);
+        assert_eq!(keeper.get(), 0.0);
+    }
+
+    #[test]
+    fn test_rolling_window_no_drift() {
+        // High price level with small moves, where E[p^2] - E[p]^2 cancels catastrophically
+        let mut state: u64 = 7;
+        let mut trades = Vec::new();
+        for i in 0..200_000u64 {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let price = 1e6 + (state >> 11) as f64 / (1u64 << 53) as f64;
+            let volume = 1.0 + (i % 7) as f64 * 0.5;
+            trades.push((price, volume, i * 10));
+        }
+        let mut keeper = VwapKeeper::with_window_ms(1000);
+        for &(price, volume, ts) in &trades {
+            keeper.add(price, volume, ts);
+        }
+
+        let start_ts = trades.last().unwrap().2 - 1000;
+        let window: Vec<_> = trades.iter().filter(|t| t.2 >= start_ts).collect();
+        let sum_volume: f64 = window.iter().map(|t| t.1).sum();
+        let vwap = window.iter().map(|t| t.0 * t.1).sum::<f64>() / sum_volume;
+        let std = (window.iter().map(|t| t.1 * (t.0 - vwap) * (t.0 - vwap)).sum::<f64>()
+            / sum_volume)
+            .sqrt();
+        assert!((keeper.get() - vwap).abs() < 1e-9);
+        assert!((keeper.get_std() - std).abs() / std < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::indicator::OhlcIndicator;
+use crate::min_max_keeper::MinMaxKeeper;
+
+/// Williams %R: where the close sits below the highest high of the last `period` bars,
+/// from 0.0 at the high to -100.0 at the low
+pub struct WilliamsRKeeper {
+    period: usize,
+    percent_r: f64,
+    prev_percent_r: f64,
+    bars: usize,
+    min_max_keeper: MinMaxKeeper,
+}
+
+impl WilliamsRKeeper {
+    pub fn new(period: usize) -> Result<Self, String> {
+        if period < 1 {
+            return Err("Williams %R period at least 1".to_string());
+        }
+
+        Ok(WilliamsRKeeper {
+            period,
+            percent_r: 0.0,
+            prev_percent_r: 0.0,
+            bars: 0,
+            // Each bar contributes its high and its low
+            min_max_keeper: MinMaxKeeper::fixed_window(period * 2),
+        })
+    }
+
+    /// Adds a bar. A flat range gives -100.0, matching the 0.0 %K of the stochastic oscillator.
+    pub fn add(&mut self, high: f64, low: f64, close: f64) -> Result<(), String> {
+        self.min_max_keeper.add(high).map_err(|e| e.to_string())?;
+        self.min_max_keeper.add(low).map_err(|e| e.to_string())?;
+        self.bars += 1;
+        let highest_high = self.min_max_keeper.get_max();
+        let lowest_low = self.min_max_keeper.get_min();
+        self.prev_percent_r = self.percent_r;
+
+        if (highest_high - lowest_low).abs() > 1e-10 {
+            self.percent_r = -100.0 * (highest_high - close) / (highest_high - lowest_low);
+        } else {
+            self.percent_r = -100.0;
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self) -> f64 {
+        self.percent_r
+    }
+
+    /// Gets %R before the latest bar
+    pub fn get_prev(&self) -> f64 {
+        self.prev_percent_r
+    }
+
+    pub fn get_period(&self) -> usize {
+        self.period
+    }
+
+    /// Clears the rolling window and %R state, keeping the period
+    pub fn reset(&mut self) {
+        self.min_max_keeper.reset();
+        self.percent_r = 0.0;
+        self.prev_percent_r = 0.0;
+        self.bars = 0;
+    }
+
+    pub fn is_overbought(&self) -> bool {
+        if self.bars < self.period {
+            return false;
+        }
+
+        self.percent_r > -20.0
+    }
+
+    pub fn is_oversold(&self) -> bool {
+        if self.bars < self.period {
+            return false;
+        }
+
+        self.percent_r < -80.0
+    }
+}
+
+impl OhlcIndicator for WilliamsRKeeper {
+    fn update_ohlcv(
+        &mut self,
+        high: f64,
+        low: f64,
+        close: f64,
+        _volume: f64,
+        _ts: u64,
+    ) -> Result<(), String> {
+        self.add(high, low, close)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_williams_r_new() {
+        assert!(WilliamsRKeeper::new(14).is_ok());
+        assert!(WilliamsRKeeper::new(0).is_err());
+    }
+
+    #[test]
+    fn test_percent_r() {
+        let mut keeper = WilliamsRKeeper::new(3).unwrap();
+        keeper.add(105.0, 100.0, 102.0).unwrap();
+        keeper.add(108.0, 101.0, 107.0).unwrap();
+        keeper.add(106.0, 103.0, 104.0).unwrap();
+        // Highest high 108, lowest low 100
+        assert!((keeper.get() - (-100.0 * 4.0 / 8.0)).abs() < 1e-12);
+
+        // The first bar leaves the window: highest high 108, lowest low 101
+        keeper.add(104.0, 102.0, 102.0).unwrap();
+        assert!((keeper.get() - (-100.0 * 6.0 / 7.0)).abs() < 1e-12);
+        assert!((keeper.get_prev() + 50.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_overbought_oversold() {
+        let mut keeper = WilliamsRKeeper::new(2).unwrap();
+        keeper.add(110.0, 100.0, 109.0).unwrap();
+        // Not enough bars yet
+        assert!(!keeper.is_overbought());
+
+        keeper.add(110.0, 100.0, 109.0).unwrap();
+        assert!(keeper.is_overbought());
+        assert!(!keeper.is_oversold());
+
+        keeper.add(110.0, 100.0, 101.0).unwrap();
+        assert!(keeper.is_oversold());
+        assert!(!keeper.is_overbought());
+    }
+
+    #[test]
+    fn test_flat_range() {
+        let mut keeper = WilliamsRKeeper::new(3).unwrap();
+        keeper.add(100.0, 100.0, 100.0).unwrap();
+        assert_eq!(keeper.get(), -100.0);
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use crate::common_utils::round_to;
+use crate::indicator::Indicator;
+
+/// Linearly weighted moving average: the newest value has weight `n`, the oldest weight 1
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WmaKeeper {
+    arr: VecDeque<f64>,
+    max_len: usize,
+    wma: f64,
+    prev_wma: f64,
+    pub prev_timestamp: u64,
+}
+
+impl WmaKeeper {
+    pub fn new(max_len: usize) -> Self {
+        WmaKeeper {
+            arr: VecDeque::with_capacity(max_len + 1),
+            max_len,
+            wma: 0.0,
+            prev_wma: 0.0,
+            prev_timestamp: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.arr.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.arr.len() == self.max_len
+    }
+
+    /// Adds a new value, updating the WMA. The timestamp is recorded for parity with
+    /// SmaKeeper but does not gate updates.
+    pub fn add(&mut self, timestamp: u64, value: f64) -> f64 {
+        self.prev_timestamp = timestamp;
+        self.arr.push_back(value);
+        while self.arr.len() > self.max_len {
+            self.arr.pop_front();
+        }
+
+        let mut weighted_sum = 0.0;
+        let mut weight_total = 0.0;
+        for (i, v) in self.arr.iter().enumerate() {
+            let weight = (i + 1) as f64;
+            weighted_sum += weight * v;
+            weight_total += weight;
+        }
+
+        self.prev_wma = self.wma;
+        self.wma = weighted_sum / weight_total;
+        self.wma
+    }
+
+    /// Gets the current WMA value
+    pub fn get(&self) -> f64 {
+        self.wma
+    }
+
+    /// Gets the current WMA rounded to the nearest multiple of `tick_size`
+    pub fn get_rounded(&self, tick_size: f64) -> f64 {
+        round_to(self.get(), tick_size)
+    }
+
+    /// Gets the previous WMA value
+    pub fn get_prev(&self) -> f64 {
+        self.prev_wma
+    }
+
+    /// Clears the window, keeping the configured length
+    pub fn reset(&mut self) {
+        self.arr.clear();
+        self.wma = 0.0;
+        self.prev_wma = 0.0;
+        self.prev_timestamp = 0;
+    }
+}
+
+impl Indicator for WmaKeeper {
+    fn update(&mut self, value: f64) -> f64 {
+        let timestamp = self.prev_timestamp + 1;
+        self.add(timestamp, value)
+    }
+
+    fn value(&self) -> f64 {
+        self.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights() {
+        let mut keeper = WmaKeeper::new(3);
+        assert_eq!(keeper.add(1, 1.0), 1.0);
+        // (1 * 1 + 2 * 4) / 3
+        assert_eq!(keeper.add(2, 4.0), 3.0);
+        // (1 * 1 + 2 * 4 + 3 * 7) / 6
+        assert_eq!(keeper.add(3, 7.0), 5.0);
+        // Oldest value rolls out: (1 * 4 + 2 * 7 + 3 * 1) / 6
+        assert_eq!(keeper.add(4, 1.0), 3.5);
+        assert_eq!(keeper.get_prev(), 5.0);
+        assert_eq!(keeper.size(), 3);
+    }
+}